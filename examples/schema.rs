@@ -4,8 +4,15 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use cw_rockpaperscissors::msg::{
-    ExecuteMsg, GetGameByPlayerResponse, GetGameByPlayersResponse, GetGamesResponse,
-    GetLeaderboardResponse, GetOpenGamesResponse, InstantiateMsg, QueryMsg,
+    ChannelSignDoc, ExecuteMsg, GetAchievementsResponse, GetCurrentSeasonResponse, GetFeesResponse,
+    GetGameByPlayerResponse, GetGameByPlayersResponse, GetGamesResponse, GetHouseGameResponse,
+    GetHouseResponse, GetLeaderboardResponse, GetLeagueResponse, GetLeagueStandingsResponse,
+    GetNftCollectionsResponse, GetOpenGamesResponse, GetPrizeDistributionResponse,
+    GetPrizePoolResponse, GetPubkeyResponse, GetReferralStatsResponse, GetRelayNonceResponse,
+    GetReliabilityResponse, GetRematchOfferResponse, GetSeasonLeaderboardResponse,
+    GetSessionKeyResponse, GetSessionKeysResponse, GetSideBetsResponse,
+    GetStreakLeaderboardResponse, GetTournamentResponse, GetTournamentsResponse,
+    GetTreasuryResponse, InstantiateMsg, MigrateMsg, QueryMsg, RelaySignDoc,
 };
 use cw_rockpaperscissors::state::{GameState, UserProfile};
 
@@ -17,12 +24,38 @@ fn main() {
 
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
     export_schema(&schema_for!(GetGameByPlayerResponse), &out_dir);
     export_schema(&schema_for!(GetGameByPlayersResponse), &out_dir);
     export_schema(&schema_for!(GetLeaderboardResponse), &out_dir);
     export_schema(&schema_for!(GetGamesResponse), &out_dir);
     export_schema(&schema_for!(GetOpenGamesResponse), &out_dir);
+    export_schema(&schema_for!(GetCurrentSeasonResponse), &out_dir);
+    export_schema(&schema_for!(GetSeasonLeaderboardResponse), &out_dir);
+    export_schema(&schema_for!(GetPrizePoolResponse), &out_dir);
+    export_schema(&schema_for!(GetPrizeDistributionResponse), &out_dir);
+    export_schema(&schema_for!(GetTournamentResponse), &out_dir);
+    export_schema(&schema_for!(GetTournamentsResponse), &out_dir);
+    export_schema(&schema_for!(GetLeagueResponse), &out_dir);
+    export_schema(&schema_for!(GetLeagueStandingsResponse), &out_dir);
+    export_schema(&schema_for!(GetRematchOfferResponse), &out_dir);
+    export_schema(&schema_for!(GetStreakLeaderboardResponse), &out_dir);
+    export_schema(&schema_for!(GetAchievementsResponse), &out_dir);
+    export_schema(&schema_for!(GetNftCollectionsResponse), &out_dir);
+    export_schema(&schema_for!(GetPubkeyResponse), &out_dir);
+    export_schema(&schema_for!(ChannelSignDoc), &out_dir);
+    export_schema(&schema_for!(GetRelayNonceResponse), &out_dir);
+    export_schema(&schema_for!(RelaySignDoc), &out_dir);
+    export_schema(&schema_for!(GetSessionKeyResponse), &out_dir);
+    export_schema(&schema_for!(GetHouseResponse), &out_dir);
+    export_schema(&schema_for!(GetHouseGameResponse), &out_dir);
+    export_schema(&schema_for!(GetTreasuryResponse), &out_dir);
+    export_schema(&schema_for!(GetFeesResponse), &out_dir);
+    export_schema(&schema_for!(GetReferralStatsResponse), &out_dir);
+    export_schema(&schema_for!(GetSideBetsResponse), &out_dir);
+    export_schema(&schema_for!(GetReliabilityResponse), &out_dir);
+    export_schema(&schema_for!(GetSessionKeysResponse), &out_dir);
     export_schema(&schema_for!(GameState), &out_dir);
     export_schema(&schema_for!(UserProfile), &out_dir);
 }
@@ -1,8 +1,9 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Order,
-    Response, StdResult,
+    from_binary, to_binary, to_vec, Addr, BankMsg, Binary, Coin, ContractResult, CosmosMsg, Deps,
+    DepsMut, Empty, Env, Event, MessageInfo, Order, Reply, Response, StdResult, Storage, SubMsg,
+    Uint128, WasmMsg,
 };
 use cw0::maybe_addr;
 use cw2::set_contract_version;
@@ -10,24 +11,81 @@ use cw2::set_contract_version;
 use sha2::Digest;
 
 use sha2::Sha256;
+use std::cmp::Ordering;
 use std::str;
 
 use crate::error::ContractError;
+use crate::events;
 use crate::msg::{
-    ExecuteMsg, GetGameByPlayerResponse, GetGameByPlayersResponse, GetGamesResponse,
-    GetLeaderboardResponse, GetOpenGamesResponse, InstantiateMsg, QueryMsg,
+    ChannelSignDoc, Cw721ExecuteMsg, Cw721ReceiveMsg, ExecuteMsg, GameEvent, GameEventHookMsg,
+    GetAchievementsResponse, GetCurrentSeasonResponse, GetFeesResponse, GetGameByPlayerResponse,
+    GetGameByPlayersResponse, GetGamesResponse, GetHouseGameResponse, GetHouseResponse,
+    GetLeaderboardResponse, GetLeagueResponse, GetLeagueStandingsResponse,
+    GetNftCollectionsResponse, GetOpenGamesResponse, GetPrizeDistributionResponse,
+    GetPrizePoolResponse, GetPubkeyResponse, GetReferralStatsResponse, GetRelayNonceResponse,
+    GetReliabilityResponse, GetRematchOfferResponse, GetSeasonLeaderboardResponse,
+    GetSessionKeyResponse, GetSessionKeysResponse, GetSideBetsResponse,
+    GetStreakLeaderboardResponse, GetTournamentResponse, GetTournamentsResponse,
+    GetTreasuryResponse, InstantiateMsg, MigrateMsg, MintMsg, QueryMsg, ReceiveNftMsg,
+    RelayMoveMsg, RelaySignDoc, RelayedMove, SignedChannelState, Trait, TrophyMetadata,
+    UnlockedAchievement,
 };
 use crate::state::{
-    game_states, leaderboard, GameMove, GameResult, GameState, PlayerMove, UnmatchedPlayer,
-    UserProfile, ADMIN, UNMATCHED_PLAYERS,
+    game_states, leaderboard, season_leaderboard, Achievement, AchievementCriteria, Asset, BetSide,
+    FeeConfig, GameMode, GameMove, GameResult, GameState, HouseChain, HouseConfig, HouseGame,
+    League, LeagueFixture, LeagueStanding, LeagueStatus, Nft, PlayerMove, PlayerResult,
+    PrizeDistribution, ReferralStats, RematchOffer, Season, SeasonProfile, SessionKey, SideBet,
+    SideBetPool, TieBreak, TiePolicy, Tournament, TournamentStatus, UnmatchedPlayer, UserProfile,
+    ACCRUED_FEES, ACHIEVEMENTS, ADMIN, COMPETITION_ESCROW, FEE_CONFIG, GAME_ESCROW, HOOKS,
+    HOUSE_BANKROLL, HOUSE_CHAIN, HOUSE_CHAIN_GAMES, HOUSE_CONFIG, HOUSE_EXPOSURE, HOUSE_GAMES,
+    LATEST_GAME_ID, LATEST_LEAGUE_ID, LATEST_SEASON_ID, LATEST_TOURNAMENT_ID, LEAGUES,
+    LEAGUE_PLAYERS, LEGACY_GAME_PLAYER1_INDEX, LEGACY_GAME_PLAYER2_INDEX, LEGACY_GAME_STATES,
+    LEGACY_UNMATCHED_PLAYERS, LIFETIME_FEES, NFT_COLLECTIONS, PAYOUT_CURVE, PENALTY_BONDS,
+    PENALTY_BOND_TOTAL, PRIZE_DISTRIBUTIONS, PRIZE_DISTRIBUTION_TOTAL, PRIZE_POOL, PUBKEYS,
+    QUEUE_DEPOSITS, REFERRALS, REFERRAL_STATS, REFERRAL_TOTAL, RELAY_NONCES, REMATCH_OFFERS,
+    SEASONS, SESSION_KEYS, SIDE_BETS, SIDE_BET_CAP, SIDE_BET_POOLS, SIDE_BET_TOTAL, TOURNAMENTS,
+    TOURNAMENT_PLAYERS, TROPHY_CONTRACT, UNLOCKED_ACHIEVEMENTS, UNMATCHED_PLAYERS,
 };
 
-use cw_storage_plus::{Bound, I32Key, U8Key};
+use cw_storage_plus::{Bound, I32Key, Item, Map, PrimaryKey, U32Key, U64Key, U8Key};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:rockpaperscissors";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// how long players have to agree on a rematch after a game, in nanoseconds
+const REMATCH_WINDOW: u64 = 5 * 60 * 1_000_000_000;
+
+// reply id for trophy mints, which are allowed to fail
+const TROPHY_MINT_REPLY_ID: u64 = 1;
+
+// reply id for hook calls, a broken hook shouldn't block games
+const HOOK_REPLY_ID: u64 = 2;
+
+// reliability players need to join the reliable-only queue, in percent
+const RELIABLE_THRESHOLD: u8 = 90;
+
+// how long a house game can sit before whoever is up to reveal forfeits, in nanoseconds
+const HOUSE_REVEAL_WINDOW: u64 = 60 * 1_000_000_000;
+
+// tied hands after which a league fixture is scored as a tied series
+const LEAGUE_MAX_TIES: u8 = 3;
+
+// how long players have to make a first move before an untouched game can be aborted, in nanoseconds
+const ABORT_WINDOW: u64 = 60 * 1_000_000_000;
+
+// how long a bracket game can sit without either player moving before the tournament
+// can be cancelled, in nanoseconds
+const TOURNAMENT_STALL_WINDOW: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+// how long newer co-signed states can still be brought in after a channel dispute, in nanoseconds
+const CHANNEL_CHALLENGE_WINDOW: u64 = 5 * 60 * 1_000_000_000;
+
+// most of a stake the fee can take, so winning always pays out more than the bet
+const MAX_FEE_PERCENTAGE: u8 = 50;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     mut deps: DepsMut,
@@ -42,9 +100,43 @@ pub fn instantiate(
     let api = deps.api;
     ADMIN.set(deps.branch(), maybe_addr(api, msg.admin)?)?;
 
+    if let Some(trophy_contract) = maybe_addr(api, msg.trophy_contract)? {
+        TROPHY_CONTRACT.save(deps.storage, &trophy_contract)?;
+    }
+
+    for achievement in default_achievements() {
+        ACHIEVEMENTS.save(deps.storage, &achievement.id, &achievement)?;
+    }
+
     Ok(Response::new().add_attribute("method", "instantiate"))
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // Contracts from before the registry, or before a default was added, are missing some.
+    // Existing definitions are left alone so admin changes aren't overwritten
+    let mut seeded = vec![];
+    for achievement in default_achievements() {
+        if !ACHIEVEMENTS.has(deps.storage, &achievement.id) {
+            ACHIEVEMENTS.save(deps.storage, &achievement.id, &achievement)?;
+            seeded.push(achievement.id);
+        }
+    }
+
+    // Games and queue entries from before stakes could be NFTs have to be readable
+    // before the totals are seeded from them
+    migrate_legacy_stakes(deps.storage)?;
+
+    // Contracts from before the running treasury totals start them off from the records
+    seed_treasury_totals(deps.storage)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("achievements_seeded", seeded.join(",")))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -54,9 +146,23 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     let api = deps.api;
     match msg {
-        ExecuteMsg::JoinGame { num_hands_to_win } => {
-            try_join_game(deps, env, info, num_hands_to_win)
-        }
+        ExecuteMsg::JoinGame {
+            num_hands_to_win,
+            tie_break,
+            mode,
+            referrer,
+            reliable_only,
+        } => try_join_game(
+            deps,
+            env,
+            info,
+            num_hands_to_win,
+            tie_break,
+            mode,
+            referrer,
+            reliable_only.unwrap_or_default(),
+        ),
+        ExecuteMsg::ReceiveNft(wrapper) => try_receive_nft(deps, env, info, wrapper),
         ExecuteMsg::LeaveWaitingQueue {} => try_leave_waiting_queue(deps, env, info),
         ExecuteMsg::CommitMove {
             player1,
@@ -69,107 +175,517 @@ pub fn execute(
             game_move,
             nonce,
         } => try_reveal_move(deps, env, info, player1, player2, game_move, nonce),
+        ExecuteMsg::RevealMoves {
+            player1,
+            player2,
+            game_moves,
+            nonce,
+        } => try_reveal_moves(deps, env, info, player1, player2, game_moves, nonce),
         ExecuteMsg::ClaimGame { player1, player2 } => {
             try_claim_game(deps, env, info, player1, player2)
         }
-        ExecuteMsg::ForfeitGame {} => try_forfeit_game(deps, env, info),
+        ExecuteMsg::AbortGame { player1, player2 } => {
+            try_abort_game(deps, env, info, player1, player2)
+        }
+        ExecuteMsg::RelayMove(relay_move) => try_relay_move(deps, env, info, relay_move),
+        ExecuteMsg::SetPubkey { pubkey } => try_set_pubkey(deps, env, info, pubkey),
+        ExecuteMsg::SettleChannel {
+            player1,
+            player2,
+            signed_state,
+        } => try_settle_channel(deps, env, info, player1, player2, signed_state),
+        ExecuteMsg::DisputeChannel {
+            player1,
+            player2,
+            signed_state,
+        } => try_dispute_channel(deps, env, info, player1, player2, signed_state),
+        ExecuteMsg::ForfeitGame { player } => try_forfeit_game(deps, env, info, player),
+        ExecuteMsg::GrantSessionKey {
+            delegate,
+            expires_at,
+            max_games,
+        } => try_grant_session_key(deps, env, info, delegate, expires_at, max_games),
+        ExecuteMsg::RevokeSessionKey { delegate } => {
+            try_revoke_session_key(deps, env, info, delegate)
+        }
+        ExecuteMsg::PlayHouse { hashed_move } => try_play_house(deps, env, info, hashed_move),
+        ExecuteMsg::RevealHouseGame { game_move, nonce } => {
+            try_reveal_house_game(deps, env, info, game_move, nonce)
+        }
+        ExecuteMsg::RevealHouseMove {
+            game_move,
+            nonce,
+            previous_link,
+        } => try_reveal_house_move(deps, env, info, game_move, nonce, previous_link),
+        ExecuteMsg::ClaimHouseGame { player } => try_claim_house_game(deps, env, info, player),
+        ExecuteMsg::CommitHouseChain { head, length } => {
+            try_commit_house_chain(deps, env, info, head, length)
+        }
+        ExecuteMsg::OfferRematch { opponent } => try_offer_rematch(deps, env, info, opponent),
+        ExecuteMsg::AcceptRematch { opponent } => try_accept_rematch(deps, env, info, opponent),
+        ExecuteMsg::CancelRematch { opponent } => try_cancel_rematch(deps, env, info, opponent),
+        ExecuteMsg::FundPrizePool {} => try_fund_prize_pool(deps, env, info),
+        ExecuteMsg::PlaceSideBet {
+            player1,
+            player2,
+            on,
+        } => try_place_side_bet(deps, env, info, player1, player2, on),
+        ExecuteMsg::ClaimReferralRewards {} => try_claim_referral_rewards(deps, env, info),
+        ExecuteMsg::PostPenaltyBond {} => try_post_penalty_bond(deps, env, info),
+        ExecuteMsg::WithdrawPenaltyBond {} => try_withdraw_penalty_bond(deps, env, info),
+        ExecuteMsg::CreateTournament {
+            size,
+            buy_in,
+            num_hands_to_win,
+            registration_deadline,
+            payout_curve,
+        } => try_create_tournament(
+            deps,
+            env,
+            info,
+            size,
+            buy_in,
+            num_hands_to_win,
+            registration_deadline,
+            payout_curve,
+        ),
+        ExecuteMsg::RegisterForTournament { tournament_id } => {
+            try_register_for_tournament(deps, env, info, tournament_id)
+        }
+        ExecuteMsg::CancelTournament { tournament_id } => {
+            try_cancel_tournament(deps, env, info, tournament_id)
+        }
+        ExecuteMsg::CreateLeague {
+            participants,
+            entry_fee,
+            num_hands_to_win,
+            join_deadline,
+            round_duration,
+        } => try_create_league(
+            deps,
+            env,
+            info,
+            participants,
+            entry_fee,
+            num_hands_to_win,
+            join_deadline,
+            round_duration,
+        ),
+        ExecuteMsg::JoinLeague { league_id } => try_join_league(deps, env, info, league_id),
+        ExecuteMsg::CancelLeague { league_id } => try_cancel_league(deps, env, info, league_id),
+        ExecuteMsg::TimeoutLeagueRound { league_id } => {
+            try_timeout_league_round(deps, env, info, league_id)
+        }
 
         // ADMIN handlers
+        ExecuteMsg::StartSeason { end_time } => try_start_season(deps, env, info, end_time),
+        ExecuteMsg::EndSeason {} => try_end_season(deps, env, info),
+        ExecuteMsg::SetPayoutCurve { payout_curve } => {
+            try_set_payout_curve(deps, env, info, payout_curve)
+        }
+        ExecuteMsg::SetSideBetCap { cap } => try_set_side_bet_cap(deps, env, info, cap),
+        ExecuteMsg::SetFeeConfig {
+            fee_percentage,
+            distribution_contract,
+            distribution_percentage,
+            referral_percentage,
+            prize_pool_percentage,
+        } => try_set_fee_config(
+            deps,
+            env,
+            info,
+            fee_percentage,
+            distribution_contract,
+            distribution_percentage,
+            referral_percentage,
+            prize_pool_percentage,
+        ),
+        ExecuteMsg::WithdrawFees { to, denom, amount } => {
+            try_withdraw_fees(deps, env, info, to, denom, amount)
+        }
+        ExecuteMsg::DistributePrizes { season_id, limit } => {
+            try_distribute_prizes(deps, env, info, season_id, limit)
+        }
+        ExecuteMsg::SetHouse {
+            operator,
+            max_bet,
+            max_exposure,
+        } => try_set_house(deps, env, info, operator, max_bet, max_exposure),
+        ExecuteMsg::DepositBankroll {} => try_deposit_bankroll(deps, env, info),
+        ExecuteMsg::WithdrawBankroll { amount, to } => {
+            try_withdraw_bankroll(deps, env, info, amount, to)
+        }
+        ExecuteMsg::AddAchievement { achievement } => {
+            try_add_achievement(deps, env, info, achievement)
+        }
+        ExecuteMsg::AddNftCollection { collection } => {
+            try_add_nft_collection(deps, env, info, collection)
+        }
+        ExecuteMsg::RemoveNftCollection { collection } => {
+            try_remove_nft_collection(deps, env, info, collection)
+        }
         ExecuteMsg::UpdateAdmin { admin } => {
             Ok(ADMIN.execute_update_admin(deps, info, maybe_addr(api, admin)?)?)
         }
+        ExecuteMsg::AddHook { addr } => {
+            Ok(HOOKS.execute_add_hook(&ADMIN, deps, info, api.addr_validate(&addr)?)?)
+        }
+        ExecuteMsg::RemoveHook { addr } => {
+            Ok(HOOKS.execute_remove_hook(&ADMIN, deps, info, api.addr_validate(&addr)?)?)
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn try_join_game(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     num_hands_to_win: u8,
+    tie_break: Option<TieBreak>,
+    mode: Option<GameMode>,
+    referrer: Option<String>,
+    reliable_only: bool,
+) -> Result<Response, ContractError> {
+    let referrer = maybe_addr(deps.api, referrer)?;
+
+    // Only players who are new to the contract can be referred, and only once
+    let referred = match referrer {
+        Some(referrer)
+            if referrer != info.sender
+                && !REFERRALS.has(deps.storage, &info.sender)
+                && leaderboard()
+                    .may_load(deps.storage, info.sender.as_bytes())?
+                    .is_none() =>
+        {
+            REFERRALS.save(deps.storage, &info.sender, &referrer)?;
+            let mut stats = REFERRAL_STATS
+                .may_load(deps.storage, &referrer)?
+                .unwrap_or_default();
+            stats.referral_count += 1;
+            save_referral_stats(deps.storage, &referrer, &stats)?;
+            Some(referrer)
+        }
+        _ => None,
+    };
+
+    let res = join_game(
+        deps,
+        env,
+        info.sender,
+        Asset::Coins(info.funds),
+        num_hands_to_win,
+        tie_break,
+        mode.unwrap_or_default(),
+        reliable_only,
+    )?;
+
+    Ok(match referred {
+        Some(referrer) => res.add_attribute("referrer", referrer),
+        None => res,
+    })
+}
+
+pub fn try_receive_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw721ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // The collection contract is the one calling us
+    if !NFT_COLLECTIONS.has(deps.storage, &info.sender) {
+        return Err(ContractError::InvalidNftCollection {});
+    }
+
+    let player = deps.api.addr_validate(&wrapper.sender)?;
+    let nft = Nft {
+        collection: info.sender,
+        token_id: wrapper.token_id,
+    };
+
+    match from_binary(&wrapper.msg)? {
+        ReceiveNftMsg::JoinGame {
+            num_hands_to_win,
+            tie_break,
+            mode,
+        } => join_game(
+            deps,
+            env,
+            player,
+            Asset::Nft(nft),
+            num_hands_to_win,
+            tie_break,
+            mode.unwrap_or_default(),
+            false,
+        ),
+    }
+}
+
+/// Helper function for queueing a player or matching them against someone already waiting
+#[allow(clippy::too_many_arguments)]
+fn join_game(
+    deps: DepsMut,
+    env: Env,
+    player: Addr,
+    stake: Asset,
+    num_hands_to_win: u8,
+    tie_break: Option<TieBreak>,
+    mode: GameMode,
+    reliable_only: bool,
 ) -> Result<Response, ContractError> {
     // Validators. can only join game if
     // - you are specified as player 2
     // - you pay the necessary funds
+    // - you aren't playing in a tournament or league
 
-    // Check if there is a player waiting with the same funds
-    let maybe_unmatched_player = UNMATCHED_PLAYERS.may_load(
-        deps.storage,
-        (format!("{:?}", info.funds), U8Key::new(num_hands_to_win)),
-    )?;
+    if let Some(TieBreak { max_ties: 0, .. }) = tie_break {
+        return Err(ContractError::InvalidTieBreak {});
+    }
+
+    // Blitz and channel games are decided by the moves or score they settle with,
+    // there's no extra hand to play
+    if mode != GameMode::Classic && tie_break.is_some() {
+        return Err(ContractError::InvalidTieBreak {});
+    }
+
+    // Channel players need a key to sign their scores with
+    if mode == GameMode::Channel && !PUBKEYS.has(deps.storage, &player) {
+        return Err(ContractError::MissingPubkey {});
+    }
+
+    if TOURNAMENT_PLAYERS.has(deps.storage, &player) || LEAGUE_PLAYERS.has(deps.storage, &player) {
+        return Err(ContractError::PlayerBusy {});
+    }
+
+    // Serial abandoners can't get into the queue of players who see their games through
+    if reliable_only {
+        let maybe_profile = leaderboard().may_load(deps.storage, player.as_bytes())?;
+        if maybe_profile.map_or(100, |profile| reliability(&profile)) < RELIABLE_THRESHOLD {
+            return Err(ContractError::Unreliable {});
+        }
+    }
+
+    // Check if there is a player waiting with the same stake and game settings
+    let key = queue_key(&stake, num_hands_to_win, &tie_break, &mode, reliable_only);
+    let maybe_unmatched_player = UNMATCHED_PLAYERS.may_load(deps.storage, key.clone())?;
 
     match maybe_unmatched_player {
         Some(unmatched_player) => {
             // Found a competitor player
 
-            let game_state = GameState {
-                player1: unmatched_player.address.clone(),
-                player2: info.sender.clone(),
-                player1_move: None,
-                player2_move: None,
-                player1_hands_won: 0,
-                player2_hands_won: 0,
-                hands_tied: 0,
-                bet_amount: info.funds.clone(),
-                player1_bet_deposited: true,
-                player2_bet_deposited: true,
-                result: None,
-                num_hands_to_win,
-                // updated_at: env.block.time.nanos() / 1_000_000,
-                updated_at: env.block.time.nanos(),
+            // NFTs come from the same collection, that's what the queue key matched on
+            let mut game_state = GameState {
+                tie_break: tie_break.clone(),
+                mode,
+                player1_stake: unmatched_player.stake.clone(),
+                ..new_game_state(
+                    &env,
+                    unmatched_player.address.clone(),
+                    player.clone(),
+                    stake,
+                    num_hands_to_win,
+                )
             };
 
-            UNMATCHED_PLAYERS.remove(
-                deps.storage,
-                (
-                    format!("{:?}", info.funds),
-                    U8Key::new(unmatched_player.num_hands_to_win),
-                ),
-            );
+            remove_unmatched_player(deps.storage, key)?;
 
-            game_states().save(
-                deps.storage,
-                (
-                    unmatched_player.address.clone().into_string().as_bytes(),
-                    info.sender.clone().into_string().as_bytes(),
-                ),
-                &game_state,
-            )?;
+            let res = save_new_game(deps.storage, &mut game_state)?;
 
             // Goal is for frontend to know when it finds a game with an opponent by reading attributes off the transaction
-            Ok(Response::new()
+            Ok(res
                 .add_attribute("action", "join_game")
                 .add_attribute(
                     "players",
-                    format!("{},{}", unmatched_player.address, info.sender),
+                    format!("{},{}", unmatched_player.address, player),
                 )
                 .add_attribute("opponent_found", "true")
-                .add_attribute("game_state", serde_json::to_string(&game_state).unwrap()))
+                .add_attribute("game_state", game_state_json(&game_state)?))
         }
         None => {
             // Didn't find a competitor
             // Add this player to the unmatched pool
 
             let user_profile = UnmatchedPlayer {
-                address: info.sender.clone(),
-                bet_amount: info.funds.clone(),
+                address: player.clone(),
+                stake,
                 num_hands_to_win,
+                tie_break,
+                mode,
+                reliable_only,
             };
 
-            UNMATCHED_PLAYERS.save(
-                deps.storage,
-                (format!("{:?}", info.funds), U8Key::new(num_hands_to_win)),
-                &user_profile,
-            )?;
+            save_unmatched_player(deps.storage, key, &user_profile)?;
 
             // Goal is for frontend to know when it finds a game with an opponent
             Ok(Response::new()
+                .add_event(events::player_queued(
+                    &player,
+                    &user_profile.stake,
+                    num_hands_to_win,
+                ))
                 .add_attribute("action", "join_game")
-                .add_attribute("players", format!("{}", info.sender))
+                .add_attribute("players", format!("{}", player))
                 .add_attribute("opponent_found", "false"))
         }
     }
 }
 
+/// Helper function for the waiting queue key, players are only matched on identical game settings
+fn queue_key(
+    stake: &Asset,
+    num_hands_to_win: u8,
+    tie_break: &Option<TieBreak>,
+    mode: &GameMode,
+    reliable_only: bool,
+) -> (String, U8Key) {
+    // Any two tokens of a collection are worth the same bet
+    let stake_key = match stake {
+        Asset::Coins(bet_amount) => format!("{:?}", bet_amount),
+        Asset::Nft(nft) => format!("nft:{}", nft.collection),
+    };
+
+    // Games without a tie cap keep their original key
+    let bet_key = match tie_break {
+        Some(tie_break) => format!("{}{:?}", stake_key, tie_break),
+        None => stake_key,
+    };
+    // and neither do classic games
+    let bet_key = match mode {
+        GameMode::Classic => bet_key,
+        GameMode::Blitz => format!("{}:blitz", bet_key),
+        GameMode::Channel => format!("{}:channel", bet_key),
+    };
+    let bet_key = if reliable_only {
+        format!("{}:reliable", bet_key)
+    } else {
+        bet_key
+    };
+    (bet_key, U8Key::new(num_hands_to_win))
+}
+
+/// Helper function for rewriting games and queue entries stored before stakes could be NFTs,
+/// their bet amount becomes the coin stake of both players
+fn migrate_legacy_stakes(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy_games = LEGACY_GAME_STATES
+        .range(storage, None, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok((_, legacy)) if legacy.bet_amount.is_none() => None,
+            item => Some(item.map(|(_, legacy)| legacy)),
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    for legacy in legacy_games {
+        let bet_amount = legacy.bet_amount.unwrap_or_default();
+        let game_state = GameState {
+            id: 0,
+            player1: legacy.player1,
+            player2: legacy.player2,
+            player1_move: legacy.player1_move,
+            player2_move: legacy.player2_move,
+            player1_hands_won: legacy.player1_hands_won,
+            player2_hands_won: legacy.player2_hands_won,
+            hands_tied: legacy.hands_tied,
+            player1_stake: Asset::Coins(bet_amount.clone()),
+            player2_stake: Asset::Coins(bet_amount),
+            player1_bet_deposited: legacy.player1_bet_deposited,
+            player2_bet_deposited: legacy.player2_bet_deposited,
+            result: legacy.result,
+            num_hands_to_win: legacy.num_hands_to_win,
+            updated_at: legacy.updated_at,
+            tournament_id: None,
+            league_id: None,
+            tie_break: None,
+            player1_moves: vec![],
+            player2_moves: vec![],
+            mode: GameMode::Classic,
+            channel_nonce: 0,
+            channel_disputed: false,
+            channel_disputed_at: 0,
+            player1_relayer_fees: vec![],
+            player2_relayer_fees: vec![],
+        };
+
+        LEGACY_GAME_PLAYER1_INDEX.remove(storage, &game_state.player1);
+        LEGACY_GAME_PLAYER2_INDEX.remove(storage, &game_state.player2);
+        game_states().replace(
+            storage,
+            (game_state.player1.as_bytes(), game_state.player2.as_bytes()),
+            Some(&game_state),
+            None,
+        )?;
+    }
+
+    let legacy_players = LEGACY_UNMATCHED_PLAYERS
+        .range(storage, None, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok((_, legacy)) if legacy.bet_amount.is_none() => None,
+            item => Some(item.map(|(_, legacy)| legacy)),
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    for legacy in legacy_players {
+        let stake = Asset::Coins(legacy.bet_amount.unwrap_or_default());
+        let key = queue_key(
+            &stake,
+            legacy.num_hands_to_win,
+            &None,
+            &GameMode::Classic,
+            false,
+        );
+        UNMATCHED_PLAYERS.save(
+            storage,
+            key,
+            &UnmatchedPlayer {
+                address: legacy.address,
+                stake,
+                num_hands_to_win: legacy.num_hands_to_win,
+                tie_break: None,
+                mode: GameMode::Classic,
+                reliable_only: false,
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Helper function for creating a game where both players already deposited the same stake
+fn new_game_state(
+    env: &Env,
+    player1: Addr,
+    player2: Addr,
+    stake: Asset,
+    num_hands_to_win: u8,
+) -> GameState {
+    GameState {
+        player1,
+        player2,
+        player1_move: None,
+        player2_move: None,
+        player1_hands_won: 0,
+        player2_hands_won: 0,
+        hands_tied: 0,
+        player1_stake: stake.clone(),
+        player2_stake: stake,
+        player1_bet_deposited: true,
+        player2_bet_deposited: true,
+        result: None,
+        num_hands_to_win,
+        // updated_at: env.block.time.nanos() / 1_000_000,
+        updated_at: env.block.time.nanos(),
+        tournament_id: None,
+        league_id: None,
+        tie_break: None,
+        player1_moves: vec![],
+        player2_moves: vec![],
+        mode: GameMode::Classic,
+        channel_nonce: 0,
+        channel_disputed: false,
+        channel_disputed_at: 0,
+        player1_relayer_fees: vec![],
+        player2_relayer_fees: vec![],
+        id: 0,
+    }
+}
+
 pub fn try_leave_waiting_queue(
     deps: DepsMut,
     _env: Env,
@@ -183,21 +699,24 @@ pub fn try_leave_waiting_queue(
     let maybe_unmatched_player = query_res.iter().find(|(_, b)| b.address == info.sender);
 
     if let Some((_, unmatched_player)) = maybe_unmatched_player {
+        let stake = unmatched_player.stake.clone();
+
         // Remove the user from the queue
-        UNMATCHED_PLAYERS.remove(
+        remove_unmatched_player(
             deps.storage,
-            (
-                format!("{:?}", unmatched_player.bet_amount),
-                U8Key::new(unmatched_player.num_hands_to_win),
+            queue_key(
+                &stake,
+                unmatched_player.num_hands_to_win,
+                &unmatched_player.tie_break,
+                &unmatched_player.mode,
+                unmatched_player.reliable_only,
             ),
-        );
+        )?;
 
-        // Send the user their money back
+        // Send the user their bet back
         Ok(Response::new()
-            .add_messages(vec![CosmosMsg::Bank(BankMsg::Send {
-                to_address: info.sender.clone().into(),
-                amount: unmatched_player.clone().bet_amount,
-            })])
+            .add_messages(send_asset(&info.sender, stake)?)
+            .add_event(events::player_left_queue(&info.sender))
             .add_attribute("action", "leave_waiting_queue")
             .add_attribute("players", format!("{}", info.sender)))
     } else {
@@ -228,6 +747,16 @@ pub fn try_commit_move(
 
     match maybe_game_state {
         Some(game_state) => {
+            // Channel games are only played on-chain once the dispute's challenge window closes
+            if channel_open(&game_state, &env) {
+                return Err(ContractError::WrongGameMode {});
+            }
+
+            let info = MessageInfo {
+                sender: acting_player(deps.storage, &env, &info.sender, &game_state)?,
+                ..info
+            };
+
             if info.sender == player1_addr {
                 // Playing for player 1
                 let updated_game_state = GameState {
@@ -237,20 +766,18 @@ pub fn try_commit_move(
                 };
 
                 // Save the updated game state
-                game_states().save(
+                save_game_state(
                     deps.storage,
                     (player1.as_bytes(), player2.as_bytes()),
                     &updated_game_state,
                 )?;
 
                 Ok(Response::new()
+                    .add_event(events::move_committed(&updated_game_state, &info.sender))
                     .add_attribute("action", "commit_move")
-                    .add_attribute("players", format!("{}{}", player1_addr, player2_addr))
+                    .add_attribute("players", format!("{},{}", player1_addr, player2_addr))
                     .add_attribute("player_committed", info.sender)
-                    .add_attribute(
-                        "game_state",
-                        serde_json::to_string(&updated_game_state).unwrap(),
-                    ))
+                    .add_attribute("game_state", game_state_json(&updated_game_state)?))
             } else if info.sender == player2_addr {
                 // Playing for player 2
                 let updated_game_state = GameState {
@@ -260,20 +787,18 @@ pub fn try_commit_move(
                 };
 
                 // Save the updated game state
-                game_states().save(
+                save_game_state(
                     deps.storage,
                     (player1.as_bytes(), player2.as_bytes()),
                     &updated_game_state,
                 )?;
 
                 Ok(Response::new()
+                    .add_event(events::move_committed(&updated_game_state, &info.sender))
                     .add_attribute("action", "commit_move")
-                    .add_attribute("players", format!("{}{}", player1_addr, player2_addr))
+                    .add_attribute("players", format!("{},{}", player1_addr, player2_addr))
                     .add_attribute("player_committed", info.sender)
-                    .add_attribute(
-                        "game_state",
-                        serde_json::to_string(&updated_game_state).unwrap(),
-                    ))
+                    .add_attribute("game_state", game_state_json(&updated_game_state)?))
             } else {
                 // Can't play for a game where you are neither player 1 nor player 2
                 Err(ContractError::Unauthorized {})
@@ -286,64 +811,82 @@ pub fn try_commit_move(
 
 pub fn update_leaderboard(
     deps: DepsMut,
-    player1_addr: Addr,
-    player2_addr: Addr,
+    env: Env,
+    game_state: &GameState,
     game_result: GameResult,
-    bet_amount: Vec<Coin>,
 ) -> Result<Response, ContractError> {
     // Update the user profiles involved to reflect winning / losing
     // but only if the game is over
+    let player1_addr = game_state.player1.clone();
+    let player2_addr = game_state.player2.clone();
+    let stake = game_state.player1_stake.clone();
 
     // Get the player 1 and player 2 profiles
     let maybe_player1_profile = leaderboard().may_load(deps.storage, player1_addr.as_bytes())?;
     let maybe_player2_profile = leaderboard().may_load(deps.storage, player2_addr.as_bytes())?;
 
     // If they don't have an entry in the leaderboard yet, get default entries
-    let mut updated_player1_profile = if let Some(player1_profile) = maybe_player1_profile {
-        player1_profile
-    } else {
-        UserProfile {
-            address: player1_addr.clone(),
-            num_games_played: 0,
-            num_games_won: 0,
-            winnings: 0,
+    let mut updated_player1_profile =
+        maybe_player1_profile.unwrap_or_else(|| new_user_profile(player1_addr.clone()));
+    let mut updated_player2_profile =
+        maybe_player2_profile.unwrap_or_else(|| new_user_profile(player2_addr.clone()));
+
+    let (player1_best_streak, player2_best_streak) = record_game_result(
+        &mut updated_player1_profile,
+        &mut updated_player2_profile,
+        &game_result,
+        &stake,
+    );
+
+    // Let the frontend celebrate new best win streaks
+    let mut res = Response::new();
+    for (extended, profile) in [
+        (player1_best_streak, &updated_player1_profile),
+        (player2_best_streak, &updated_player2_profile),
+    ] {
+        if extended {
+            res = res.add_event(events::win_streak(
+                &profile.address,
+                profile.best_win_streak,
+            ));
         }
-    };
+    }
 
-    let mut updated_player2_profile = if let Some(player2_profile) = maybe_player2_profile {
-        player2_profile
-    } else {
-        UserProfile {
-            address: player2_addr.clone(),
-            num_games_played: 0,
-            num_games_won: 0,
-            winnings: 0,
-        }
+    // Ranks are taken before this game moves the leaderboard
+    let top_players = get_leaderboard(deps.as_ref(), None, Some(30))?.leaderboard;
+    let rank = |address: &Addr| {
+        top_players
+            .iter()
+            .position(|profile| &profile.address == address)
+            .map(|position| position as u32 + 1)
     };
+    let player1_rank = rank(&player1_addr);
+    let player2_rank = rank(&player2_addr);
 
-    // Increment num games played for both players
-    updated_player1_profile.num_games_played += 1;
-    updated_player2_profile.num_games_played += 1;
-
-    if let GameResult::Player1Wins = game_result {
-        // Increment num games 1 for player 1
-        updated_player1_profile.num_games_won += 1;
-
-        // Add to player 1 winnings
-        updated_player1_profile.winnings += bet_amount[0].amount.u128() as i32;
-
-        // Subtract from player 2 winnings
-        updated_player2_profile.winnings -= bet_amount[0].amount.u128() as i32;
-    } else {
-        // Increment num games 1 for player 1
-        updated_player2_profile.num_games_won += 1;
-
-        // Add to player 1 winnings
-        updated_player2_profile.winnings += bet_amount[0].amount.u128() as i32;
+    let hands_played = (game_state.player1_hands_won
+        + game_state.player2_hands_won
+        + game_state.hands_tied) as usize;
 
-        // Subtract from player 2 winnings
-        updated_player1_profile.winnings -= bet_amount[0].amount.u128() as i32;
-    };
+    res = res.add_events(unlock_achievements(
+        deps.storage,
+        &env,
+        &updated_player1_profile,
+        game_state.player1_hands_won == game_state.num_hands_to_win
+            && game_state.player1_moves.len() == hands_played,
+        &game_state.player1_moves,
+        player2_rank,
+        0,
+    )?);
+    res = res.add_events(unlock_achievements(
+        deps.storage,
+        &env,
+        &updated_player2_profile,
+        game_state.player2_hands_won == game_state.num_hands_to_win
+            && game_state.player2_moves.len() == hands_played,
+        &game_state.player2_moves,
+        player1_rank,
+        0,
+    )?);
 
     // Save user profiles to the leaderboard
     leaderboard().save(
@@ -358,7 +901,274 @@ pub fn update_leaderboard(
         &updated_player2_profile,
     )?;
 
-    Ok(Response::new())
+    // Mirror the result into the season leaderboard if a season is running
+    if let Some(season) = active_season(deps.storage, &env)? {
+        let player1_season_key = (U32Key::new(season.id), player1_addr.as_bytes());
+        let player2_season_key = (U32Key::new(season.id), player2_addr.as_bytes());
+
+        let mut player1_season_profile = season_leaderboard()
+            .may_load(deps.storage, player1_season_key.clone())?
+            .map(|season_profile| season_profile.profile)
+            .unwrap_or_else(|| new_user_profile(player1_addr.clone()));
+        let mut player2_season_profile = season_leaderboard()
+            .may_load(deps.storage, player2_season_key.clone())?
+            .map(|season_profile| season_profile.profile)
+            .unwrap_or_else(|| new_user_profile(player2_addr.clone()));
+
+        record_game_result(
+            &mut player1_season_profile,
+            &mut player2_season_profile,
+            &game_result,
+            &stake,
+        );
+
+        season_leaderboard().save(
+            deps.storage,
+            player1_season_key,
+            &SeasonProfile {
+                season_id: season.id,
+                profile: player1_season_profile,
+            },
+        )?;
+
+        season_leaderboard().save(
+            deps.storage,
+            player2_season_key,
+            &SeasonProfile {
+                season_id: season.id,
+                profile: player2_season_profile,
+            },
+        )?;
+    }
+
+    Ok(res)
+}
+
+/// Helper function for unlocking the achievements a player earned with a hand or a finished game
+fn unlock_achievements(
+    storage: &mut dyn Storage,
+    env: &Env,
+    profile: &UserProfile,
+    won_by_hands: bool,
+    game_moves: &[GameMove],
+    opponent_rank: Option<u32>,
+    hand_streak: u8,
+) -> StdResult<Vec<Event>> {
+    let won = profile.last_result == Some(PlayerResult::Won);
+
+    let achievements = ACHIEVEMENTS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut events = vec![];
+    for (_, achievement) in achievements {
+        let key = (profile.address.as_bytes(), achievement.id.as_bytes());
+        if UNLOCKED_ACHIEVEMENTS.has(storage, key) {
+            continue;
+        }
+
+        let unlocked = match &achievement.criteria {
+            AchievementCriteria::GamesWon { count } => profile.num_games_won >= *count,
+            AchievementCriteria::WinStreak { length } => profile.best_win_streak >= *length,
+            AchievementCriteria::BeatTopPlayer { rank } => {
+                won && matches!(opponent_rank, Some(opponent_rank) if opponent_rank <= *rank)
+            }
+            AchievementCriteria::SingleMoveWin { game_move } => {
+                won && won_by_hands
+                    && !game_moves.is_empty()
+                    && game_moves.iter().all(|played| played == game_move)
+            }
+            AchievementCriteria::HandStreak { length } => hand_streak >= *length,
+        };
+
+        if unlocked {
+            UNLOCKED_ACHIEVEMENTS.save(storage, key, &env.block.time.nanos())?;
+            events.push(events::achievement_unlocked(
+                &profile.address,
+                &achievement.id,
+            ));
+        }
+    }
+
+    Ok(events)
+}
+
+/// Helper function for creating an empty leaderboard entry
+fn new_user_profile(address: Addr) -> UserProfile {
+    UserProfile {
+        address,
+        num_games_played: 0,
+        num_games_won: 0,
+        num_games_drawn: 0,
+        winnings: 0,
+        current_streak: 0,
+        best_win_streak: 0,
+        last_result: None,
+        games_abandoned: 0,
+        games_forfeited: 0,
+    }
+}
+
+/// Helper function for the percentage of a player's games they didn't abandon or forfeit
+fn reliability(profile: &UserProfile) -> u8 {
+    if profile.num_games_played == 0 {
+        return 100;
+    }
+    let completed = profile
+        .num_games_played
+        .saturating_sub(profile.games_abandoned + profile.games_forfeited);
+    (completed as u64 * 100 / profile.num_games_played as u64) as u8
+}
+
+/// Helper function for recording that a player walked away from a game, before it's settled.
+/// Abandoning also slashes the player's penalty bond to their opponent, or into the bankroll
+/// when the opponent is the house
+fn record_walkout(
+    storage: &mut dyn Storage,
+    player: &Addr,
+    opponent: Option<&Addr>,
+    abandoned: bool,
+) -> StdResult<Response> {
+    let mut profile = leaderboard()
+        .may_load(storage, player.as_bytes())?
+        .unwrap_or_else(|| new_user_profile(player.clone()));
+    if abandoned {
+        profile.games_abandoned += 1;
+    } else {
+        profile.games_forfeited += 1;
+    }
+    leaderboard().save(storage, player.as_bytes(), &profile)?;
+
+    let res = Response::new();
+    if !abandoned {
+        return Ok(res);
+    }
+    let bond = match PENALTY_BONDS.may_load(storage, player)? {
+        Some(bond) => bond,
+        None => return Ok(res),
+    };
+    remove_penalty_bond(storage, player)?;
+
+    let res = match opponent {
+        Some(opponent) => res.add_message(BankMsg::Send {
+            to_address: opponent.to_string(),
+            amount: bond.clone(),
+        }),
+        None => {
+            let mut bankroll = HOUSE_BANKROLL.may_load(storage)?.unwrap_or_default();
+            add_coins(&mut bankroll, &bond);
+            HOUSE_BANKROLL.save(storage, &bankroll)?;
+            res
+        }
+    };
+    Ok(res.add_attribute("penalty_bond_slashed", events::coins_to_string(&bond)))
+}
+
+/// Helper function for finding who is holding up a game and who is left waiting on them,
+/// if it's only one of the players
+fn stalled_by(game_state: &GameState) -> Option<(&Addr, &Addr)> {
+    match (&game_state.player1_move, &game_state.player2_move) {
+        (Some(PlayerMove::GameMove(_)), Some(PlayerMove::HashedMove(_)))
+        | (Some(PlayerMove::GameMoves(_)), Some(PlayerMove::HashedMove(_)))
+        | (Some(PlayerMove::HashedMove(_)), None) => {
+            Some((&game_state.player2, &game_state.player1))
+        }
+        (Some(PlayerMove::HashedMove(_)), Some(PlayerMove::GameMove(_)))
+        | (Some(PlayerMove::HashedMove(_)), Some(PlayerMove::GameMoves(_)))
+        | (None, Some(PlayerMove::HashedMove(_))) => {
+            Some((&game_state.player1, &game_state.player2))
+        }
+        _ => None,
+    }
+}
+
+/// Helper function for applying a finished game to both players' profiles
+fn record_game_result(
+    player1_profile: &mut UserProfile,
+    player2_profile: &mut UserProfile,
+    game_result: &GameResult,
+    stake: &Asset,
+) -> (bool, bool) {
+    // Bracket games are played for the tournament prize pool rather than a bet,
+    // and NFT wagers don't have an amount to count
+    let amount = stake_coins(stake)
+        .first()
+        .map_or(0, |coin| coin.amount.u128() as i32);
+
+    // Increment num games played for both players
+    player1_profile.num_games_played += 1;
+    player2_profile.num_games_played += 1;
+
+    match game_result {
+        GameResult::Player1Wins => {
+            // Increment num games 1 for player 1
+            player1_profile.num_games_won += 1;
+
+            // Add to player 1 winnings
+            player1_profile.winnings += amount;
+
+            // Subtract from player 2 winnings
+            player2_profile.winnings -= amount;
+        }
+        GameResult::Player2Wins => {
+            // Increment num games 1 for player 1
+            player2_profile.num_games_won += 1;
+
+            // Add to player 1 winnings
+            player2_profile.winnings += amount;
+
+            // Subtract from player 2 winnings
+            player1_profile.winnings -= amount;
+        }
+        GameResult::Tie => {
+            // Increment num games drawn for both players
+            player1_profile.num_games_drawn += 1;
+            player2_profile.num_games_drawn += 1;
+
+            // No winnings change hands on a draw
+        }
+    };
+
+    let (player1_result, player2_result) = match game_result {
+        GameResult::Player1Wins => (PlayerResult::Won, PlayerResult::Lost),
+        GameResult::Player2Wins => (PlayerResult::Lost, PlayerResult::Won),
+        GameResult::Tie => (PlayerResult::Drew, PlayerResult::Drew),
+    };
+
+    (
+        update_streak(player1_profile, player1_result),
+        update_streak(player2_profile, player2_result),
+    )
+}
+
+/// Helper function for updating a player's streak, returns whether they extended their best win streak
+fn update_streak(profile: &mut UserProfile, player_result: PlayerResult) -> bool {
+    profile.current_streak = match player_result {
+        PlayerResult::Won => profile.current_streak.max(0) + 1,
+        PlayerResult::Lost => profile.current_streak.min(0) - 1,
+        // A draw breaks either streak
+        PlayerResult::Drew => 0,
+    };
+    profile.last_result = Some(player_result);
+
+    if profile.current_streak > profile.best_win_streak as i32 {
+        profile.best_win_streak = profile.current_streak as u32;
+        true
+    } else {
+        false
+    }
+}
+
+/// Helper function for loading the season that game results currently count towards
+fn active_season(storage: &dyn Storage, env: &Env) -> StdResult<Option<Season>> {
+    let maybe_season = match LATEST_SEASON_ID.may_load(storage)? {
+        Some(season_id) => SEASONS.may_load(storage, U32Key::new(season_id))?,
+        None => None,
+    };
+
+    let now = env.block.time.nanos();
+    Ok(maybe_season
+        .filter(|season| !season.ended && season.start_time <= now && now < season.end_time))
 }
 
 pub fn try_reveal_move(
@@ -385,6 +1195,17 @@ pub fn try_reveal_move(
 
     match maybe_game_state {
         Some(game_state) => {
+            // Blitz games reveal every move at once
+            // and channel games are only played on-chain once the dispute's challenge window closes
+            if game_state.mode == GameMode::Blitz || channel_open(&game_state, &env) {
+                return Err(ContractError::WrongGameMode {});
+            }
+
+            let info = MessageInfo {
+                sender: acting_player(deps.storage, &env, &info.sender, &game_state)?,
+                ..info
+            };
+
             let mut updated_game_state = GameState {
                 updated_at: env.block.time.nanos(),
                 ..game_state.clone()
@@ -426,19 +1247,19 @@ pub fn try_reveal_move(
 
                     // Handle result accordingly
                     handle_hand_result(
+                        deps.storage,
+                        &env,
                         &mut updated_game_state,
                         player1_game_move,
                         player2_game_move,
+                        false,
                     )
                 } else {
                     Ok(Response::new()
                         .add_attribute("action", "reveal_move")
-                        .add_attribute("players", format!("{}{}", player1_addr, player2_addr))
-                        .add_attribute("player_revealed", info.sender)
-                        .add_attribute(
-                            "game_state",
-                            serde_json::to_string(&updated_game_state).unwrap(),
-                        ))
+                        .add_attribute("players", format!("{},{}", player1_addr, player2_addr))
+                        .add_attribute("player_revealed", info.sender.clone())
+                        .add_attribute("game_state", game_state_json(&updated_game_state)?))
                 };
 
                 res
@@ -478,19 +1299,19 @@ pub fn try_reveal_move(
 
                     // Handle result accordingly
                     handle_hand_result(
+                        deps.storage,
+                        &env,
                         &mut updated_game_state,
                         player1_game_move,
                         player2_game_move,
+                        true,
                     )
                 } else {
                     Ok(Response::new()
                         .add_attribute("action", "reveal_move")
-                        .add_attribute("players", format!("{}{}", player1_addr, player2_addr))
-                        .add_attribute("player_revealed", info.sender)
-                        .add_attribute(
-                            "game_state",
-                            serde_json::to_string(&updated_game_state).unwrap(),
-                        ))
+                        .add_attribute("players", format!("{},{}", player1_addr, player2_addr))
+                        .add_attribute("player_revealed", info.sender.clone())
+                        .add_attribute("game_state", game_state_json(&updated_game_state)?))
                 };
 
                 res
@@ -499,33 +1320,71 @@ pub fn try_reveal_move(
                 return Err(ContractError::Unauthorized {});
             };
 
+            let res = res
+                .map(|res| res.add_event(events::move_revealed(&updated_game_state, &info.sender)));
+
+            // Let hooks know when both moves are in and the hand is played out
+            let res = match (
+                updated_game_state.player1_moves.last(),
+                updated_game_state.player2_moves.last(),
+            ) {
+                (Some(player1_move), Some(player2_move))
+                    if updated_game_state.player1_moves.len() > game_state.player1_moves.len() =>
+                {
+                    let hand_result = if updated_game_state.player1_hands_won
+                        > game_state.player1_hands_won
+                    {
+                        GameResult::Player1Wins
+                    } else if updated_game_state.player2_hands_won > game_state.player2_hands_won {
+                        GameResult::Player2Wins
+                    } else {
+                        GameResult::Tie
+                    };
+
+                    let event = events::hand_resolved(
+                        &updated_game_state,
+                        player1_move,
+                        player2_move,
+                        &hand_result,
+                    );
+                    let hooks = game_event_hooks(
+                        deps.storage,
+                        GameEvent::HandResolved {
+                            player1: updated_game_state.player1.clone(),
+                            player2: updated_game_state.player2.clone(),
+                            player1_move: player1_move.clone(),
+                            player2_move: player2_move.clone(),
+                            result: hand_result,
+                        },
+                    )?;
+                    res.map(|res| res.add_event(event).add_submessages(hooks))
+                }
+                _ => res,
+            };
+
             // Handle state updates depending on whether or not the game is complete
-            if let Some(game_result) = updated_game_state.result {
+            if let Some(game_result) = updated_game_state.result.clone() {
                 // The game is over
                 // so remove the game from the game states
-                game_states().remove(deps.storage, (player1.as_bytes(), player2.as_bytes()))?;
-
-                // Update the leaderboard based on the final state of the game
-                update_leaderboard(
-                    deps,
-                    player1_addr,
-                    player2_addr,
-                    game_result,
-                    updated_game_state.bet_amount,
-                )?;
+                remove_game_state(deps.storage, (player1.as_bytes(), player2.as_bytes()))?;
+
+                // Pay out and update the leaderboard based on the final state of the game
+                let settlement = settle_game(deps, env, &updated_game_state, game_result)?;
+
+                Ok(merge_responses(res?, settlement))
             } else {
                 // The game is not over
                 // so update the game state
 
-                game_states().save(
+                save_game_state(
                     deps.storage,
                     (player1.as_bytes(), player2.as_bytes()),
                     &updated_game_state,
                 )?;
-            }
 
-            // Return the response / error
-            res
+                // Return the response / error
+                res
+            }
         }
         None => {
             // Game doesn't exist
@@ -534,16 +1393,504 @@ pub fn try_reveal_move(
     }
 }
 
-pub fn try_claim_game(
+pub fn try_reveal_moves(
     deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     player1: String,
     player2: String,
+    game_moves: Vec<GameMove>,
+    nonce: String,
 ) -> Result<Response, ContractError> {
+    // Validators. Can only reveal moves if:
+    // - the game is a blitz game
+    // - you are either player 1 or player 2
+    // - you committed to exactly these moves
+
     let player1_addr = deps.api.addr_validate(&player1)?;
     let player2_addr = deps.api.addr_validate(&player2)?;
 
+    let mut game_state = game_states()
+        .may_load(deps.storage, (player1.as_bytes(), player2.as_bytes()))?
+        .ok_or(ContractError::InvalidGame {})?;
+
+    if game_state.mode != GameMode::Blitz {
+        return Err(ContractError::WrongGameMode {});
+    }
+
+    let info = MessageInfo {
+        sender: acting_player(deps.storage, &env, &info.sender, &game_state)?,
+        ..info
+    };
+
+    // One move for every hand the game could possibly take
+    if game_moves.len() != (game_state.num_hands_to_win as usize * 2).saturating_sub(1) {
+        return Err(ContractError::InvalidMoveSequence {});
+    }
+
+    let committed_move = if info.sender == player1_addr {
+        &game_state.player1_move
+    } else if info.sender == player2_addr {
+        &game_state.player2_move
+    } else {
+        // Can't reveal moves for a game you don't belong to
+        return Err(ContractError::Unauthorized {});
+    };
+
+    // Verify that the hashes match up
+    let moves_hash = format!(
+        "{:x}",
+        Sha256::digest(format!("{}{}", moves_to_string(&game_moves), nonce).as_bytes())
+    );
+    match committed_move {
+        Some(PlayerMove::HashedMove(hashed_move)) if *hashed_move == moves_hash => {}
+        _ => return Err(ContractError::Unauthorized {}),
+    }
+
+    if info.sender == player1_addr {
+        game_state.player1_move = Some(PlayerMove::GameMoves(game_moves));
+    } else {
+        game_state.player2_move = Some(PlayerMove::GameMoves(game_moves));
+    }
+    game_state.updated_at = env.block.time.nanos();
+
+    let mut res = Response::new()
+        .add_event(events::move_revealed(&game_state, &info.sender))
+        .add_attribute("action", "reveal_moves")
+        .add_attribute("players", format!("{},{}", player1_addr, player2_addr))
+        .add_attribute("player_revealed", info.sender);
+
+    // Wait for the opponent to reveal their moves
+    let (player1_moves, player2_moves) = match (&game_state.player1_move, &game_state.player2_move)
+    {
+        (
+            Some(PlayerMove::GameMoves(player1_moves)),
+            Some(PlayerMove::GameMoves(player2_moves)),
+        ) => (player1_moves.clone(), player2_moves.clone()),
+        _ => {
+            save_game_state(
+                deps.storage,
+                (player1.as_bytes(), player2.as_bytes()),
+                &game_state,
+            )?;
+
+            return Ok(res.add_attribute("game_state", game_state_json(&game_state)?));
+        }
+    };
+    game_state.player1_move = None;
+    game_state.player2_move = None;
+
+    // Play out the hands in order until someone has won enough of them
+    for (player1_move, player2_move) in player1_moves.into_iter().zip(player2_moves) {
+        let hand_result = get_result(player1_move.clone(), player2_move.clone());
+        match hand_result {
+            GameResult::Player1Wins => game_state.player1_hands_won += 1,
+            GameResult::Player2Wins => game_state.player2_hands_won += 1,
+            GameResult::Tie => game_state.hands_tied += 1,
+        }
+        game_state.player1_moves.push(player1_move.clone());
+        game_state.player2_moves.push(player2_move.clone());
+
+        res = res
+            .add_event(events::hand_resolved(
+                &game_state,
+                &player1_move,
+                &player2_move,
+                &hand_result,
+            ))
+            .add_submessages(game_event_hooks(
+                deps.storage,
+                GameEvent::HandResolved {
+                    player1: game_state.player1.clone(),
+                    player2: game_state.player2.clone(),
+                    player1_move,
+                    player2_move,
+                    result: hand_result,
+                },
+            )?);
+
+        if game_state.player1_hands_won == game_state.num_hands_to_win
+            || game_state.player2_hands_won == game_state.num_hands_to_win
+        {
+            break;
+        }
+    }
+
+    // Ties can use up the hands before anyone gets there, then whoever won more hands wins
+    let game_result = match game_state
+        .player1_hands_won
+        .cmp(&game_state.player2_hands_won)
+    {
+        Ordering::Greater => GameResult::Player1Wins,
+        Ordering::Less => GameResult::Player2Wins,
+        Ordering::Equal => GameResult::Tie,
+    };
+    game_state.result = Some(game_result.clone());
+
+    // The game is over
+    // so remove the game from the game states
+    remove_game_state(deps.storage, (player1.as_bytes(), player2.as_bytes()))?;
+
+    let res = res.add_attribute("game_state", game_state_json(&game_state)?);
+    let settlement = settle_game(deps, env, &game_state, game_result)?;
+
+    Ok(merge_responses(res, settlement))
+}
+
+/// Helper function for the string a blitz commitment is made over, e.g. Rock,Paper,Rock
+fn moves_to_string(game_moves: &[GameMove]) -> String {
+    game_moves
+        .iter()
+        .map(|game_move| game_move.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn try_relay_move(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    relay_move: RelayMoveMsg,
+) -> Result<Response, ContractError> {
+    // Validators. Can only relay a move if:
+    // - the player is either player 1 or player 2
+    // - the player signed this move for this hand of this game
+    // - the relay nonce is the player's next one
+    // - the fee leaves some of the player's bet
+
+    let player = deps.api.addr_validate(&relay_move.player)?;
+
+    let mut game_state = game_states()
+        .may_load(
+            deps.storage,
+            (relay_move.player1.as_bytes(), relay_move.player2.as_bytes()),
+        )?
+        .ok_or(ContractError::InvalidGame {})?;
+
+    if player != game_state.player1 && player != game_state.player2 {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Binding the game and hand stops a signed move being replayed anywhere else
+    let sign_doc = RelaySignDoc {
+        contract: env.contract.address.to_string(),
+        game_id: game_state.id,
+        hand: game_state.player1_hands_won as u32
+            + game_state.player2_hands_won as u32
+            + game_state.hands_tied as u32
+            + 1,
+        relayed_move: relay_move.relayed_move.clone(),
+        relay_nonce: relay_move.relay_nonce,
+        fee: relay_move.fee.clone(),
+    };
+    let pubkey = PUBKEYS
+        .may_load(deps.storage, &player)?
+        .ok_or(ContractError::MissingPubkey {})?;
+    if !deps.api.secp256k1_verify(
+        &Sha256::digest(&to_vec(&sign_doc)?),
+        &relay_move.signature,
+        &pubkey,
+    )? {
+        return Err(ContractError::InvalidSignature {});
+    }
+
+    let relay_nonce = RELAY_NONCES
+        .may_load(deps.storage, &player)?
+        .unwrap_or_default();
+    if relay_move.relay_nonce != relay_nonce {
+        return Err(ContractError::InvalidRelayNonce {});
+    }
+
+    let mut res = Response::new().add_attribute("relayed_by", info.sender.clone());
+
+    if !relay_move.fee.is_empty() {
+        let fees_paid = if player == game_state.player1 {
+            &mut game_state.player1_relayer_fees
+        } else {
+            &mut game_state.player2_relayer_fees
+        };
+
+        // The fee comes out of what's left of the player's own bet
+        let mut total_fees = fees_paid.clone();
+        add_coins(&mut total_fees, &relay_move.fee);
+        let stake = if player == game_state.player1 {
+            &game_state.player1_stake
+        } else {
+            &game_state.player2_stake
+        };
+        let remaining_bet = sub_coins(&stake_coins(stake), &total_fees)
+            .ok_or(ContractError::InvalidRelayerFee {})?;
+        if relay_move.fee.iter().any(|coin| coin.amount.is_zero())
+            || remaining_bet.iter().any(|coin| coin.amount.is_zero())
+        {
+            return Err(ContractError::InvalidRelayerFee {});
+        }
+        *fees_paid = total_fees;
+
+        save_game_state(
+            deps.storage,
+            (relay_move.player1.as_bytes(), relay_move.player2.as_bytes()),
+            &game_state,
+        )?;
+
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.into(),
+            amount: relay_move.fee,
+        });
+    }
+
+    RELAY_NONCES.save(deps.storage, &player, &(relay_nonce + 1))?;
+
+    // Play the move as if the player sent it themselves
+    let player_info = MessageInfo {
+        sender: player,
+        funds: vec![],
+    };
+    let move_res = match relay_move.relayed_move {
+        RelayedMove::CommitMove { hashed_move } => try_commit_move(
+            deps.branch(),
+            env,
+            player_info,
+            relay_move.player1,
+            relay_move.player2,
+            hashed_move,
+        )?,
+        RelayedMove::RevealMove { game_move, nonce } => try_reveal_move(
+            deps.branch(),
+            env,
+            player_info,
+            relay_move.player1,
+            relay_move.player2,
+            game_move,
+            nonce,
+        )?,
+    };
+
+    Ok(merge_responses(res, move_res))
+}
+
+pub fn try_set_pubkey(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    pubkey: Binary,
+) -> Result<Response, ContractError> {
+    // Compressed or uncompressed secp256k1 keys
+    if pubkey.len() != 33 && pubkey.len() != 65 {
+        return Err(ContractError::InvalidPubkey {});
+    }
+
+    // A new key would let the player disown the states they already signed
+    let player1_game = game_states()
+        .idx
+        .player1
+        .item(deps.storage, info.sender.clone())?;
+    let player2_game = game_states()
+        .idx
+        .player2
+        .item(deps.storage, info.sender.clone())?;
+    if player1_game
+        .into_iter()
+        .chain(player2_game)
+        .any(|(_, game_state)| channel_open(&game_state, &env))
+    {
+        return Err(ContractError::PubkeyInUse {});
+    }
+
+    PUBKEYS.save(deps.storage, &info.sender, &pubkey)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_pubkey")
+        .add_attribute("player", info.sender))
+}
+
+pub fn try_settle_channel(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    player1: String,
+    player2: String,
+    signed_state: SignedChannelState,
+) -> Result<Response, ContractError> {
+    // Validators. Can only settle a channel game if:
+    // - you are either player 1 or player 2
+    // - both players signed the state
+    // - someone has won the game in that state
+
+    let mut game_state = load_channel_game(deps.as_ref(), &env, &info, &player1, &player2)?;
+    apply_channel_state(deps.as_ref(), &env, &mut game_state, &signed_state)?;
+
+    let game_result = if game_state.player1_hands_won == game_state.num_hands_to_win {
+        GameResult::Player1Wins
+    } else if game_state.player2_hands_won == game_state.num_hands_to_win {
+        GameResult::Player2Wins
+    } else {
+        return Err(ContractError::InvalidChannelState {});
+    };
+    game_state.result = Some(game_result.clone());
+
+    // The game is over
+    // so remove the game from the game states
+    remove_game_state(deps.storage, (player1.as_bytes(), player2.as_bytes()))?;
+
+    let res = Response::new()
+        .add_attribute("action", "settle_channel")
+        .add_attribute("players", format!("{},{}", player1, player2))
+        .add_attribute("settled_by", info.sender)
+        .add_attribute("game_state", game_state_json(&game_state)?);
+    let settlement = settle_game(deps, env, &game_state, game_result)?;
+
+    Ok(merge_responses(res, settlement))
+}
+
+pub fn try_dispute_channel(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    player1: String,
+    player2: String,
+    signed_state: Option<SignedChannelState>,
+) -> Result<Response, ContractError> {
+    // Validators. Can only dispute a channel game if:
+    // - you are either player 1 or player 2
+    // - both players signed the state, if there is one
+    // - nobody has won the game in that state, that should be settled instead
+    // - the game isn't disputed yet, or you bring a newer state within the challenge window
+
+    let mut game_state = load_channel_game(deps.as_ref(), &env, &info, &player1, &player2)?;
+
+    match signed_state {
+        Some(signed_state) => {
+            apply_channel_state(deps.as_ref(), &env, &mut game_state, &signed_state)?;
+
+            if game_state.player1_hands_won == game_state.num_hands_to_win
+                || game_state.player2_hands_won == game_state.num_hands_to_win
+            {
+                return Err(ContractError::InvalidChannelState {});
+            }
+        }
+        None if game_state.channel_disputed => return Err(ContractError::InvalidChannelState {}),
+        None => {}
+    }
+
+    // The rest of the game is played with commits and reveals once the challenge window closes.
+    // Newer states brought in during the window don't move it, nor the claim timeout
+    if !game_state.channel_disputed {
+        game_state.channel_disputed = true;
+        game_state.channel_disputed_at = env.block.time.nanos();
+        game_state.updated_at = env.block.time.nanos();
+    }
+
+    save_game_state(
+        deps.storage,
+        (player1.as_bytes(), player2.as_bytes()),
+        &game_state,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "dispute_channel")
+        .add_attribute("players", format!("{},{}", player1, player2))
+        .add_attribute("disputed_by", info.sender)
+        .add_attribute("game_state", game_state_json(&game_state)?))
+}
+
+/// Helper function for loading a channel game on behalf of one of its players
+fn load_channel_game(
+    deps: Deps,
+    env: &Env,
+    info: &MessageInfo,
+    player1: &str,
+    player2: &str,
+) -> Result<GameState, ContractError> {
+    let game_state = game_states()
+        .may_load(deps.storage, (player1.as_bytes(), player2.as_bytes()))?
+        .ok_or(ContractError::InvalidGame {})?;
+
+    if info.sender != game_state.player1 && info.sender != game_state.player2 {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if game_state.mode != GameMode::Channel {
+        return Err(ContractError::WrongGameMode {});
+    }
+
+    // Signed states can't override hands played on-chain after the challenge window
+    if !channel_open(&game_state, env) {
+        return Err(ContractError::ChannelDisputed {});
+    }
+
+    Ok(game_state)
+}
+
+/// Helper function for whether a channel game still takes signed states, which it does
+/// until the challenge window after a dispute closes
+fn channel_open(game_state: &GameState, env: &Env) -> bool {
+    game_state.mode == GameMode::Channel
+        && (!game_state.channel_disputed
+            || env.block.time.nanos() <= game_state.channel_disputed_at + CHANNEL_CHALLENGE_WINDOW)
+}
+
+/// Helper function for checking both signatures on a channel state and applying its score
+fn apply_channel_state(
+    deps: Deps,
+    env: &Env,
+    game_state: &mut GameState,
+    signed_state: &SignedChannelState,
+) -> Result<(), ContractError> {
+    let state = &signed_state.state;
+
+    // Older states can't replace a newer one, nor can states of other games
+    if state.game_id != game_state.id
+        || state.nonce <= game_state.channel_nonce
+        || state.player1_hands_won > game_state.num_hands_to_win
+        || state.player2_hands_won > game_state.num_hands_to_win
+        || (state.player1_hands_won == game_state.num_hands_to_win
+            && state.player2_hands_won == game_state.num_hands_to_win)
+    {
+        return Err(ContractError::InvalidChannelState {});
+    }
+
+    // Binding the contract address stops states being replayed on another deployment
+    let sign_doc = ChannelSignDoc {
+        contract: env.contract.address.to_string(),
+        state: state.clone(),
+    };
+    let message_hash = Sha256::digest(&to_vec(&sign_doc)?);
+
+    for (player, signature) in [
+        (&game_state.player1, &signed_state.player1_signature),
+        (&game_state.player2, &signed_state.player2_signature),
+    ] {
+        let pubkey = PUBKEYS
+            .may_load(deps.storage, player)?
+            .ok_or(ContractError::MissingPubkey {})?;
+
+        if !deps
+            .api
+            .secp256k1_verify(&message_hash, signature, &pubkey)?
+        {
+            return Err(ContractError::InvalidSignature {});
+        }
+    }
+
+    game_state.player1_hands_won = state.player1_hands_won;
+    game_state.player2_hands_won = state.player2_hands_won;
+    game_state.hands_tied = state.hands_tied;
+    game_state.channel_nonce = state.nonce;
+
+    Ok(())
+}
+
+pub fn try_claim_game(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    player1: String,
+    player2: String,
+) -> Result<Response, ContractError> {
+    deps.api.addr_validate(&player1)?;
+    deps.api.addr_validate(&player2)?;
+
     let maybe_game_state =
         game_states().may_load(deps.storage, (player1.as_bytes(), player2.as_bytes()))?;
 
@@ -553,56 +1900,58 @@ pub fn try_claim_game(
 
             // Can only claim a game if it's been 1 minute since the game was last updated
             if game_state.updated_at + one_minute < env.block.time.nanos() {
-                match (game_state.player1_move, game_state.player2_move) {
+                match (&game_state.player1_move, &game_state.player2_move) {
                     (Some(PlayerMove::GameMove(_)), Some(PlayerMove::HashedMove(_)))
+                    | (Some(PlayerMove::GameMoves(_)), Some(PlayerMove::HashedMove(_)))
                     | (Some(PlayerMove::HashedMove(_)), None) => {
                         // Player 1 is stuck because player 2 is refusing to reveal
                         // or
                         // Player 1 is stuck because player 2 is refusing to make a move
 
                         // Delete the game
-                        game_states()
-                            .remove(deps.storage, (player1.as_bytes(), player2.as_bytes()))?;
-
-                        // Update leaderboard to reflect that player1 "won"
-                        update_leaderboard(
-                            deps,
-                            player1_addr.clone(),
-                            player2_addr,
-                            GameResult::Player1Wins,
-                            game_state.bet_amount.clone(),
+                        remove_game_state(deps.storage, (player1.as_bytes(), player2.as_bytes()))?;
+
+                        let walkout = record_walkout(
+                            deps.storage,
+                            &game_state.player2,
+                            Some(&game_state.player1),
+                            true,
                         )?;
 
-                        // Pay the winner
-                        Ok(send_double_tokens(player1_addr, game_state.bet_amount)
-                            .add_attribute("action", "claim_game")
-                            .add_attribute("players", format!("{}{}", player1, player2))
-                            .add_attribute("game_claimed_by", player1))
+                        // Pay the winner and update leaderboard to reflect that player1 "won"
+                        Ok(merge_responses(
+                            settle_game(deps, env, &game_state, GameResult::Player1Wins)?,
+                            walkout,
+                        )
+                        .add_attribute("action", "claim_game")
+                        .add_attribute("players", format!("{},{}", player1, player2))
+                        .add_attribute("game_claimed_by", player1))
                     }
                     (Some(PlayerMove::HashedMove(_)), Some(PlayerMove::GameMove(_)))
+                    | (Some(PlayerMove::HashedMove(_)), Some(PlayerMove::GameMoves(_)))
                     | (None, Some(PlayerMove::HashedMove(_))) => {
                         // Player 2 is stuck because player 1 is refusing to reveal
                         // or
                         // Player 2 is stuck because player 1 is refusing to make a move
 
                         // Delete the game
-                        game_states()
-                            .remove(deps.storage, (player1.as_bytes(), player2.as_bytes()))?;
-
-                        // Update the leaderboard to reflect that player2 "won"
-                        update_leaderboard(
-                            deps,
-                            player1_addr,
-                            player2_addr.clone(),
-                            GameResult::Player2Wins,
-                            game_state.bet_amount.clone(),
+                        remove_game_state(deps.storage, (player1.as_bytes(), player2.as_bytes()))?;
+
+                        let walkout = record_walkout(
+                            deps.storage,
+                            &game_state.player1,
+                            Some(&game_state.player2),
+                            true,
                         )?;
 
-                        // Pay the winner
-                        Ok(send_double_tokens(player2_addr, game_state.bet_amount)
-                            .add_attribute("action", "claim_game")
-                            .add_attribute("players", format!("{}{}", player1, player2))
-                            .add_attribute("game_claimed_by", player2))
+                        // Pay the winner and update the leaderboard to reflect that player2 "won"
+                        Ok(merge_responses(
+                            settle_game(deps, env, &game_state, GameResult::Player2Wins)?,
+                            walkout,
+                        )
+                        .add_attribute("action", "claim_game")
+                        .add_attribute("players", format!("{},{}", player1, player2))
+                        .add_attribute("game_claimed_by", player2))
                     }
                     (_, _) => Err(ContractError::Unauthorized {}),
                 }
@@ -616,11 +1965,80 @@ pub fn try_claim_game(
     }
 }
 
+pub fn try_abort_game(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    player1: String,
+    player2: String,
+) -> Result<Response, ContractError> {
+    // Validators. Can only abort a game if:
+    // - you are either player 1 or player 2
+    // - no hand has been played and neither player has committed a move
+    // - it's been 1 minute since the game started
+    // A stalled tournament game gets the tournament cancelled instead, league games have their
+    // own timeout, and channel games are played off-chain so an empty board doesn't mean
+    // nobody has moved
+
+    let game_state = game_states()
+        .may_load(deps.storage, (player1.as_bytes(), player2.as_bytes()))?
+        .ok_or(ContractError::InvalidGame {})?;
+
+    if info.sender != game_state.player1 && info.sender != game_state.player2 {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if game_state.mode == GameMode::Channel {
+        return Err(ContractError::WrongGameMode {});
+    }
+
+    if game_state.tournament_id.is_some()
+        || game_state.league_id.is_some()
+        || game_state.player1_hands_won + game_state.player2_hands_won + game_state.hands_tied > 0
+        || game_state.player1_move.is_some()
+        || game_state.player2_move.is_some()
+        || game_state.updated_at + ABORT_WINDOW >= env.block.time.nanos()
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    remove_game_state(deps.storage, (player1.as_bytes(), player2.as_bytes()))?;
+
+    // Nobody played, so the leaderboard is left alone
+    let side_bet_refunds = settle_side_bets(deps.storage, game_state.id, None)?;
+
+    Ok(send_refunds(&game_state)?
+        .add_messages(side_bet_refunds)
+        .add_event(events::game_aborted(&game_state, &info.sender))
+        .add_attribute("action", "abort_game")
+        .add_attribute("players", format!("{},{}", player1, player2))
+        .add_attribute("game_aborted_by", info.sender))
+}
+
 pub fn try_forfeit_game(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
+    player: Option<String>,
 ) -> Result<Response, ContractError> {
+    // A session key forfeits the game of the player it acts for
+    let info = match player {
+        Some(player) if player != info.sender.as_str() => {
+            let game_state = get_game_by_player(deps.as_ref(), player.clone())?
+                .game
+                .ok_or(ContractError::InvalidGame {})?;
+            let acting_for = acting_player(deps.storage, &env, &info.sender, &game_state)?;
+            if acting_for != player {
+                return Err(ContractError::Unauthorized {});
+            }
+            MessageInfo {
+                sender: acting_for,
+                ..info
+            }
+        }
+        _ => info,
+    };
+
     // Check if there exists a game where player is player1
     let maybe_game_tuple1 = game_states()
         .idx
@@ -629,30 +2047,29 @@ pub fn try_forfeit_game(
 
     if let Some((_, game_state)) = maybe_game_tuple1 {
         // Delete the game
-        game_states().remove(
+        remove_game_state(
             deps.storage,
             (game_state.player1.as_bytes(), game_state.player2.as_bytes()),
         )?;
 
-        // Update the leaderboard to reflect the forfeit
-        update_leaderboard(
-            deps,
-            game_state.player1.clone(),
-            game_state.player2.clone(),
-            GameResult::Player2Wins,
-            game_state.bet_amount.clone(),
+        let walkout = record_walkout(
+            deps.storage,
+            &game_state.player1,
+            Some(&game_state.player2),
+            false,
         )?;
 
-        // Pay the winner
-        return Ok(
-            send_double_tokens(game_state.player2.clone(), game_state.bet_amount)
-                .add_attribute("action", "forfeit_game")
-                .add_attribute(
-                    "players",
-                    format!("{}{}", game_state.player1, game_state.player2),
-                )
-                .add_attribute("game_forfeit_by", info.sender),
-        );
+        // Pay the winner and update the leaderboard to reflect the forfeit
+        return Ok(merge_responses(
+            settle_game(deps, env, &game_state, GameResult::Player2Wins)?,
+            walkout,
+        )
+        .add_attribute("action", "forfeit_game")
+        .add_attribute(
+            "players",
+            format!("{},{}", game_state.player1, game_state.player2),
+        )
+        .add_attribute("game_forfeit_by", info.sender));
     }
 
     // Check if there exists a game where player is player2
@@ -663,532 +2080,8914 @@ pub fn try_forfeit_game(
 
     if let Some((_, game_state)) = maybe_game_tuple2 {
         // Delete the game
-        game_states().remove(
+        remove_game_state(
             deps.storage,
             (game_state.player1.as_bytes(), game_state.player2.as_bytes()),
         )?;
 
-        // Update the leaderboard to reflect the forfeit
-        update_leaderboard(
-            deps,
-            game_state.player1.clone(),
-            game_state.player2.clone(),
-            GameResult::Player1Wins,
-            game_state.bet_amount.clone(),
+        let walkout = record_walkout(
+            deps.storage,
+            &game_state.player2,
+            Some(&game_state.player1),
+            false,
         )?;
 
-        // Pay the winner
-        return Ok(
-            send_double_tokens(game_state.player1.clone(), game_state.bet_amount)
-                .add_attribute("action", "forfeit_game")
-                .add_attribute(
-                    "players",
-                    format!("{}{}", game_state.player1, game_state.player2),
-                )
-                .add_attribute("game_forfeit_by", info.sender),
-        );
+        // Pay the winner and update the leaderboard to reflect the forfeit
+        return Ok(merge_responses(
+            settle_game(deps, env, &game_state, GameResult::Player1Wins)?,
+            walkout,
+        )
+        .add_attribute("action", "forfeit_game")
+        .add_attribute(
+            "players",
+            format!("{},{}", game_state.player1, game_state.player2),
+        )
+        .add_attribute("game_forfeit_by", info.sender));
     }
 
     // Game doesn't exist
     Err(ContractError::InvalidGame {})
 }
 
-/// Helper function for getting a game result based on host and opp moves
-fn get_result(host_move: GameMove, opp_move: GameMove) -> GameResult {
-    match (host_move, opp_move) {
-        // rock and paper
-        (GameMove::Rock, GameMove::Paper) => GameResult::Player2Wins,
-        (GameMove::Paper, GameMove::Rock) => GameResult::Player1Wins,
-        // paper and scissors
-        (GameMove::Paper, GameMove::Scissors) => GameResult::Player2Wins,
-        (GameMove::Scissors, GameMove::Paper) => GameResult::Player1Wins,
-        // scissors and rock
-        (GameMove::Scissors, GameMove::Rock) => GameResult::Player2Wins,
-        (GameMove::Rock, GameMove::Scissors) => GameResult::Player1Wins,
-        // remaining are ties
-        (GameMove::Rock, GameMove::Rock) => GameResult::Tie,
-        (GameMove::Scissors, GameMove::Scissors) => GameResult::Tie,
-        (GameMove::Paper, GameMove::Paper) => GameResult::Tie,
+pub fn try_grant_session_key(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    delegate: String,
+    expires_at: u64,
+    max_games: u32,
+) -> Result<Response, ContractError> {
+    let delegate = deps.api.addr_validate(&delegate)?;
+
+    // A key that's already expired or can't play a game is no use
+    if delegate == info.sender || expires_at <= env.block.time.nanos() || max_games == 0 {
+        return Err(ContractError::InvalidSessionKey {});
     }
-}
 
-/// Helper function for sending 2 times a coin amount to an address
-fn send_double_tokens(to_address: Addr, amount: Vec<Coin>) -> Response {
-    // let attributes = vec![attr("action", action), attr("to", to_address.clone())];
+    // Granting the same delegate again starts the key over
+    let session_key = SessionKey {
+        player: info.sender.clone(),
+        delegate: delegate.clone(),
+        expires_at,
+        max_games,
+        game_ids: vec![],
+    };
+    SESSION_KEYS.save(
+        deps.storage,
+        (info.sender.as_bytes(), delegate.as_bytes()),
+        &session_key,
+    )?;
 
-    Response::new().add_messages(vec![
-        CosmosMsg::Bank(BankMsg::Send {
-            to_address: to_address.clone().into(),
-            amount: amount.clone(),
-        }),
-        CosmosMsg::Bank(BankMsg::Send {
-            to_address: to_address.into(),
-            amount,
-        }),
-    ])
+    Ok(Response::new()
+        .add_attribute("action", "grant_session_key")
+        .add_attribute("player", info.sender)
+        .add_attribute("delegate", delegate))
 }
 
-// Function for making appriopriate payments and emitting apprioriate message attributes
-// based on the result of a hand
-fn handle_hand_result(
-    updated_game_state: &mut GameState,
-    player1_game_move: GameMove,
-    player2_game_move: GameMove,
+pub fn try_revoke_session_key(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    delegate: String,
 ) -> Result<Response, ContractError> {
-    let result = get_result(player1_game_move.clone(), player2_game_move.clone());
-    match result {
-        GameResult::Player1Wins => {
-            // increment player 1 win counter
-            updated_game_state.player1_hands_won += 1;
-            // check if game is over
-            if updated_game_state.player1_hands_won == updated_game_state.num_hands_to_win {
-                updated_game_state.result = Some(result);
+    let delegate = deps.api.addr_validate(&delegate)?;
+    let key = (info.sender.as_bytes(), delegate.as_bytes());
 
-                // pay the winner
-                Ok(send_double_tokens(
-                    updated_game_state.clone().player1,
-                    updated_game_state.clone().bet_amount,
-                )
-                .add_attribute("action", "reveal_move")
-                .add_attribute(
-                    "players",
-                    format!(
-                        "{}{}",
-                        updated_game_state.clone().player1,
-                        updated_game_state.clone().player2
-                    ),
-                )
-                .add_attribute("game_won", updated_game_state.clone().player1)
-                .add_attribute("player1_game_move", player1_game_move.to_string())
-                .add_attribute("player2_game_move", player2_game_move.to_string())
-                .add_attribute(
-                    "game_state",
-                    serde_json::to_string(&updated_game_state).unwrap(),
-                ))
-            } else {
-                Ok(Response::new()
-                    .add_attribute("action", "reveal_move")
-                    .add_attribute(
-                        "players",
-                        format!(
-                            "{}{}",
-                            updated_game_state.clone().player1,
-                            updated_game_state.clone().player2
-                        ),
-                    )
-                    .add_attribute("hand_won", updated_game_state.clone().player1)
-                    .add_attribute("player1_game_move", player1_game_move.to_string())
-                    .add_attribute("player2_game_move", player2_game_move.to_string())
-                    .add_attribute(
-                        "game_state",
-                        serde_json::to_string(&updated_game_state).unwrap(),
-                    ))
-            }
-        }
-        GameResult::Player2Wins => {
-            // increment player 2 win counter
-            updated_game_state.player2_hands_won += 1;
-            // check if game is over
-            if updated_game_state.player2_hands_won == updated_game_state.num_hands_to_win {
-                updated_game_state.result = Some(result);
+    if !SESSION_KEYS.has(deps.storage, key) {
+        return Err(ContractError::InvalidSessionKey {});
+    }
+    SESSION_KEYS.remove(deps.storage, key);
 
-                // pay the winner
-                Ok(send_double_tokens(
-                    updated_game_state.clone().player2,
-                    updated_game_state.clone().bet_amount,
-                )
-                .add_attribute("action", "reveal_move")
-                .add_attribute(
-                    "players",
-                    format!(
-                        "{}{}",
-                        updated_game_state.clone().player1,
-                        updated_game_state.clone().player2
-                    ),
-                )
-                .add_attribute("game_won", updated_game_state.clone().player2)
-                .add_attribute("player1_game_move", player1_game_move.to_string())
-                .add_attribute("player2_game_move", player2_game_move.to_string())
-                .add_attribute(
-                    "game_state",
-                    serde_json::to_string(&updated_game_state).unwrap(),
-                ))
+    Ok(Response::new()
+        .add_attribute("action", "revoke_session_key")
+        .add_attribute("player", info.sender)
+        .add_attribute("delegate", delegate))
+}
+
+/// Helper function for who a message acts for, either a player of the game or whoever granted the sender a session key
+fn acting_player(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    game_state: &GameState,
+) -> Result<Addr, ContractError> {
+    if sender == &game_state.player1 || sender == &game_state.player2 {
+        return Ok(sender.clone());
+    }
+
+    // Keys that can't be used are skipped, but explain the failure if no other key works
+    let mut usable = vec![];
+    let mut err = ContractError::Unauthorized {};
+    for player in [&game_state.player1, &game_state.player2] {
+        let key = (player.as_bytes(), sender.as_bytes());
+        if let Some(session_key) = SESSION_KEYS.may_load(storage, key)? {
+            if env.block.time.nanos() > session_key.expires_at {
+                err = ContractError::SessionKeyExpired {};
+            } else if !session_key.game_ids.contains(&game_state.id)
+                && session_key.game_ids.len() as u32 >= session_key.max_games
+            {
+                err = ContractError::SessionKeyExhausted {};
             } else {
-                Ok(Response::new()
-                    .add_attribute("action", "reveal_move")
-                    .add_attribute(
-                        "players",
-                        format!(
-                            "{}{}",
-                            updated_game_state.clone().player1,
-                            updated_game_state.clone().player2
-                        ),
-                    )
-                    .add_attribute("hand_won", updated_game_state.clone().player2)
-                    .add_attribute("player1_game_move", player1_game_move.to_string())
-                    .add_attribute("player2_game_move", player2_game_move.to_string())
-                    .add_attribute(
-                        "game_state",
-                        serde_json::to_string(&updated_game_state).unwrap(),
-                    ))
+                usable.push((player, session_key));
             }
         }
-        GameResult::Tie => {
-            // increment tie counter
-            updated_game_state.hands_tied += 1;
-            Ok(Response::new()
-                .add_attribute("action", "reveal_move")
-                .add_attribute(
-                    "players",
-                    format!(
-                        "{}{}",
-                        updated_game_state.clone().player1,
-                        updated_game_state.clone().player2
-                    ),
-                )
-                .add_attribute("hand_won", "tie")
-                .add_attribute("player1_game_move", player1_game_move.to_string())
-                .add_attribute("player2_game_move", player2_game_move.to_string())
-                .add_attribute(
-                    "game_state",
-                    serde_json::to_string(&updated_game_state).unwrap(),
-                ))
-        }
     }
-}
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetGameByPlayer { player } => to_binary(&get_game_by_player(deps, player)?),
-        QueryMsg::GetGameByPlayers { player1, player2 } => {
-            to_binary(&get_game(deps, player1, player2)?)
-        }
-        QueryMsg::GetLeaderboard { start_after, limit } => {
-            to_binary(&get_leaderboard(deps, start_after, limit)?)
-        }
-        QueryMsg::GetOpenGames { start_after, limit } => {
-            to_binary(&get_open_games(deps, start_after, limit)?)
-        }
-        QueryMsg::GetGames { start_after, limit } => {
-            to_binary(&get_games(deps, start_after, limit)?)
+    match usable.pop() {
+        // A delegate of both players can't tell us which one it means
+        Some(_) if !usable.is_empty() => Err(ContractError::SessionKeyAmbiguous {}),
+        Some((player, mut session_key)) => {
+            // The first move in a game uses up one of the key's games
+            if !session_key.game_ids.contains(&game_state.id) {
+                session_key.game_ids.push(game_state.id);
+                SESSION_KEYS.save(
+                    storage,
+                    (player.as_bytes(), sender.as_bytes()),
+                    &session_key,
+                )?;
+            }
+
+            Ok(player.clone())
         }
-        QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
+        // Can't play for a game where you are neither player nor their session key
+        None => Err(err),
     }
 }
 
-pub fn get_game(
-    deps: Deps,
-    player1: String,
-    player2: String,
-) -> StdResult<GetGameByPlayersResponse> {
-    let maybe_game_state =
-        game_states().may_load(deps.storage, (player1.as_bytes(), player2.as_bytes()))?;
+pub fn try_offer_rematch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    opponent: String,
+) -> Result<Response, ContractError> {
+    let opponent_addr = deps.api.addr_validate(&opponent)?;
+    let rematch_offer = load_rematch_offer(deps.storage, &info.sender, &opponent_addr)?;
 
-    match maybe_game_state {
-        Some(game_state) => Ok(GetGameByPlayersResponse {
-            game: Some(game_state),
-        }),
-        None => Ok(GetGameByPlayersResponse { game: None }),
+    if env.block.time.nanos() > rematch_offer.expires_at {
+        return Err(ContractError::RematchOfferExpired {});
+    }
+
+    // Someone already offered, the other player should accept instead
+    if rematch_offer.offered_by.is_some() {
+        return Err(ContractError::InvalidRematchOffer {});
+    }
+
+    if info.funds != rematch_offer.bet_amount {
+        return Err(ContractError::IncorrectFunds {});
     }
+
+    if player_is_busy(deps.storage, &info.sender)? {
+        return Err(ContractError::PlayerBusy {});
+    }
+
+    save_rematch_offer(
+        deps.storage,
+        (
+            rematch_offer.player1.as_bytes(),
+            rematch_offer.player2.as_bytes(),
+        ),
+        &RematchOffer {
+            offered_by: Some(info.sender.clone()),
+            ..rematch_offer.clone()
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "offer_rematch")
+        .add_attribute(
+            "players",
+            format!("{},{}", rematch_offer.player1, rematch_offer.player2),
+        )
+        .add_attribute("rematch_offered_by", info.sender))
 }
 
-pub fn get_game_by_player(deps: Deps, player: String) -> StdResult<GetGameByPlayerResponse> {
-    let player_addr = deps.api.addr_validate(&player)?;
+pub fn try_accept_rematch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    opponent: String,
+) -> Result<Response, ContractError> {
+    let opponent_addr = deps.api.addr_validate(&opponent)?;
+    let rematch_offer = load_rematch_offer(deps.storage, &info.sender, &opponent_addr)?;
 
-    // check if there exists a game where player is player1
-    let maybe_game_tuple1 = game_states()
-        .idx
-        .player1
-        .item(deps.storage, player_addr.clone())?;
+    // Can only accept an offer the opponent made
+    if rematch_offer.offered_by != Some(opponent_addr.clone()) {
+        return Err(ContractError::InvalidRematchOffer {});
+    }
 
-    if let Some((_, game_state)) = maybe_game_tuple1 {
-        return Ok(GetGameByPlayerResponse {
-            game: Some(game_state),
-            waiting_for_opponent: false,
-        });
+    if env.block.time.nanos() > rematch_offer.expires_at {
+        return Err(ContractError::RematchOfferExpired {});
     }
 
-    // check if there exists a game where player is player2
-    let maybe_game_tuple2 = game_states()
-        .idx
-        .player2
-        .item(deps.storage, player_addr.clone())?;
+    if info.funds != rematch_offer.bet_amount {
+        return Err(ContractError::IncorrectFunds {});
+    }
 
-    if let Some((_, game_state)) = maybe_game_tuple2 {
-        return Ok(GetGameByPlayerResponse {
-            game: Some(game_state),
-            waiting_for_opponent: false,
-        });
+    if player_is_busy(deps.storage, &info.sender)? || player_is_busy(deps.storage, &opponent_addr)?
+    {
+        return Err(ContractError::PlayerBusy {});
     }
 
-    // check if the player is waiting for a game
-    let query_res = UNMATCHED_PLAYERS
-        .range(deps.storage, None, None, Order::Ascending)
-        .collect::<StdResult<Vec<_>>>()?;
+    remove_rematch_offer(
+        deps.storage,
+        (
+            rematch_offer.player1.as_bytes(),
+            rematch_offer.player2.as_bytes(),
+        ),
+    )?;
 
-    let player_is_unmatched = query_res
-        .iter()
-        .map(|(_, b)| b.address.clone())
-        .any(|x| x == player_addr);
+    // Start the new game straight away, skipping the waiting queue
+    let mut game_state = GameState {
+        tie_break: rematch_offer.tie_break,
+        mode: rematch_offer.mode,
+        ..new_game_state(
+            &env,
+            rematch_offer.player1.clone(),
+            rematch_offer.player2.clone(),
+            Asset::Coins(rematch_offer.bet_amount),
+            rematch_offer.num_hands_to_win,
+        )
+    };
 
-    if player_is_unmatched {
-        Ok(GetGameByPlayerResponse {
-            game: None,
-            waiting_for_opponent: true,
-        })
+    let res = save_new_game(deps.storage, &mut game_state)?;
+
+    Ok(res
+        .add_attribute("action", "accept_rematch")
+        .add_attribute(
+            "players",
+            format!("{},{}", rematch_offer.player1, rematch_offer.player2),
+        )
+        .add_attribute("opponent_found", "true")
+        .add_attribute("game_state", game_state_json(&game_state)?))
+}
+
+pub fn try_cancel_rematch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    opponent: String,
+) -> Result<Response, ContractError> {
+    let opponent_addr = deps.api.addr_validate(&opponent)?;
+    let rematch_offer = load_rematch_offer(deps.storage, &info.sender, &opponent_addr)?;
+
+    // Only the player who made the offer can take it back
+    if rematch_offer.offered_by != Some(info.sender.clone()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let key = (
+        rematch_offer.player1.as_bytes(),
+        rematch_offer.player2.as_bytes(),
+    );
+    if env.block.time.nanos() > rematch_offer.expires_at {
+        remove_rematch_offer(deps.storage, key)?;
     } else {
-        Ok(GetGameByPlayerResponse {
-            game: None,
-            waiting_for_opponent: false,
-        })
+        // The opponent can still make an offer of their own
+        save_rematch_offer(
+            deps.storage,
+            key,
+            &RematchOffer {
+                offered_by: None,
+                ..rematch_offer.clone()
+            },
+        )?;
     }
+
+    // Send the player their bet back
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.clone().into(),
+            amount: rematch_offer.bet_amount,
+        })
+        .add_attribute("action", "cancel_rematch")
+        .add_attribute("players", format!("{},{}", info.sender, opponent_addr)))
 }
 
-pub fn get_leaderboard(
-    deps: Deps,
-    _start_after: Option<String>,
-    limit: Option<u32>,
-) -> StdResult<GetLeaderboardResponse> {
-    let limit = limit.unwrap_or(10).min(30) as usize;
+/// Helper function for loading the rematch offer between two players in either order
+fn load_rematch_offer(
+    storage: &dyn Storage,
+    player: &Addr,
+    opponent: &Addr,
+) -> Result<RematchOffer, ContractError> {
+    let maybe_rematch_offer =
+        match REMATCH_OFFERS.may_load(storage, (player.as_bytes(), opponent.as_bytes()))? {
+            Some(rematch_offer) => Some(rematch_offer),
+            None => REMATCH_OFFERS.may_load(storage, (opponent.as_bytes(), player.as_bytes()))?,
+        };
+
+    maybe_rematch_offer.ok_or(ContractError::InvalidRematchOffer {})
+}
 
-    let zero_winnings_key = leaderboard()
-        .idx
-        .winnings
-        .index_key((I32Key::from(0), b"".to_vec()));
-    let most_negative_key = leaderboard()
-        .idx
-        .winnings
-        .index_key((I32Key::from(-2147483648), b"".to_vec()));
+pub fn try_set_house(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    operator: String,
+    max_bet: Vec<Coin>,
+    max_exposure: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
 
-    let res = leaderboard()
-        .idx
-        .winnings
-        .range(
-            deps.storage,
-            Some(Bound::inclusive(zero_winnings_key)),
-            Some(Bound::exclusive(most_negative_key)),
-            Order::Descending,
-        )
-        .take(limit)
-        .collect::<StdResult<Vec<_>>>()?;
+    let operator = deps.api.addr_validate(&operator)?;
 
-    let leaderboard = res.iter().map(|(_, b)| b.clone()).collect();
+    // The chain belongs to the operator, so it can only hand over once no game is waiting on it
+    if let Some(config) = HOUSE_CONFIG.may_load(deps.storage)? {
+        if config.operator != operator {
+            if house_chain_busy(deps.storage) {
+                return Err(ContractError::HouseGamesOpen {});
+            }
+            HOUSE_CHAIN.remove(deps.storage);
+        }
+    }
 
-    Ok(GetLeaderboardResponse { leaderboard })
+    HOUSE_CONFIG.save(
+        deps.storage,
+        &HouseConfig {
+            operator: operator.clone(),
+            max_bet,
+            max_exposure,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_house")
+        .add_attribute("operator", operator))
 }
 
-pub fn get_open_games(
-    deps: Deps,
-    _start_after: Option<String>,
-    limit: Option<u32>,
-) -> StdResult<GetOpenGamesResponse> {
-    let limit = limit.unwrap_or(10).min(30) as usize;
+pub fn try_deposit_bankroll(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
 
-    let res = UNMATCHED_PLAYERS
-        .range(deps.storage, None, None, Order::Ascending)
-        .take(limit)
-        .collect::<StdResult<Vec<_>>>()?;
+    if info.funds.is_empty() {
+        return Err(ContractError::IncorrectFunds {});
+    }
 
-    let open_games = res.iter().map(|(_, b)| b.clone()).collect();
+    let mut bankroll = HOUSE_BANKROLL.may_load(deps.storage)?.unwrap_or_default();
+    add_coins(&mut bankroll, &info.funds);
+    HOUSE_BANKROLL.save(deps.storage, &bankroll)?;
 
-    Ok(GetOpenGamesResponse { open_games })
+    Ok(Response::new()
+        .add_attribute("action", "deposit_bankroll")
+        .add_attribute("amount", events::coins_to_string(&info.funds)))
 }
 
-pub fn get_games(
-    deps: Deps,
-    _start_after: Option<String>,
-    limit: Option<u32>,
-) -> StdResult<GetGamesResponse> {
-    let limit = limit.unwrap_or(10).min(30) as usize;
+pub fn try_withdraw_bankroll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Vec<Coin>,
+    to: Option<String>,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
 
-    let res = game_states()
-        .range(deps.storage, None, None, Order::Ascending)
-        .take(limit)
-        .collect::<StdResult<Vec<_>>>()?;
+    let to = maybe_addr(deps.api, to)?.unwrap_or(info.sender);
 
-    let game_states = res.iter().map(|(_, b)| b.clone()).collect();
+    if amount.is_empty() || amount.iter().any(|coin| coin.amount.is_zero()) {
+        return Err(ContractError::IncorrectFunds {});
+    }
+
+    // Only what's in the bankroll can go, stakes locked in house games stay
+    let mut bankroll = sub_coins(
+        &HOUSE_BANKROLL.may_load(deps.storage)?.unwrap_or_default(),
+        &amount,
+    )
+    .ok_or(ContractError::InsufficientBankroll {})?;
+
+    // Taking out of the bankroll lowers the balance and liabilities alike,
+    // so this only stops the house from drawing on funds that are already short
+    if !get_treasury(deps.as_ref(), env)?.solvent {
+        return Err(ContractError::InsufficientBankroll {});
+    }
+
+    bankroll.retain(|coin| !coin.amount.is_zero());
+    HOUSE_BANKROLL.save(deps.storage, &bankroll)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: amount.clone(),
+        })
+        .add_attribute("action", "withdraw_bankroll")
+        .add_attribute("to", to)
+        .add_attribute("amount", events::coins_to_string(&amount)))
+}
+
+pub fn try_commit_house_chain(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    head: String,
+    length: u64,
+) -> Result<Response, ContractError> {
+    let config = HOUSE_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::HouseUnavailable {})?;
+    if info.sender != config.operator {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Games waiting on the old chain could never be revealed against a new one
+    if house_chain_busy(deps.storage) {
+        return Err(ContractError::HouseGamesOpen {});
+    }
+
+    HOUSE_CHAIN.save(
+        deps.storage,
+        &HouseChain {
+            head: head.clone(),
+            length,
+            dealt: 0,
+            revealed: 0,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "commit_house_chain")
+        .add_attribute("head", head)
+        .add_attribute("moves_available", length.to_string()))
+}
+
+/// Helper function for checking whether any house game still waits on the house chain
+fn house_chain_busy(storage: &dyn Storage) -> bool {
+    HOUSE_CHAIN_GAMES
+        .range(storage, None, None, Order::Ascending)
+        .next()
+        .is_some()
+}
+
+pub fn try_play_house(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    hashed_move: String,
+) -> Result<Response, ContractError> {
+    // Validators. Can only play the house if:
+    // - you don't have a house game going already
+    // - the bet is fine by the house's max bet, bankroll and exposure limit
+    // - the operator's chain has a move that hasn't been dealt yet
+
+    if HOUSE_GAMES.has(deps.storage, &info.sender) {
+        return Err(ContractError::PlayerBusy {});
+    }
+
+    if info.funds.is_empty() || info.funds.iter().any(|coin| coin.amount.is_zero()) {
+        return Err(ContractError::IncorrectFunds {});
+    }
+
+    let config = HOUSE_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::HouseUnavailable {})?;
+    let mut chain = HOUSE_CHAIN.may_load(deps.storage)?.unwrap_or_default();
+    if chain.dealt >= chain.length {
+        return Err(ContractError::HouseUnavailable {});
+    }
+
+    // The house matches the bet, moving it from the bankroll into exposure
+    if sub_coins(&config.max_bet, &info.funds).is_none() {
+        return Err(ContractError::HouseLimitExceeded {});
+    }
+    let bankroll = sub_coins(
+        &HOUSE_BANKROLL.may_load(deps.storage)?.unwrap_or_default(),
+        &info.funds,
+    )
+    .ok_or(ContractError::HouseLimitExceeded {})?;
+    let mut exposure = HOUSE_EXPOSURE.may_load(deps.storage)?.unwrap_or_default();
+    add_coins(&mut exposure, &info.funds);
+    if sub_coins(&config.max_exposure, &exposure).is_none() {
+        return Err(ContractError::HouseLimitExceeded {});
+    }
+
+    // Deal the next position of the chain, its move is revealed in chain order
+    let chain_position = chain.dealt;
+    chain.dealt += 1;
+
+    let house_game = HouseGame {
+        id: next_game_id(deps.storage)?,
+        player: info.sender.clone(),
+        bet_amount: info.funds,
+        player_move: PlayerMove::HashedMove(hashed_move),
+        chain_position,
+        updated_at: env.block.time.nanos(),
+    };
+
+    HOUSE_CHAIN.save(deps.storage, &chain)?;
+    HOUSE_CHAIN_GAMES.save(deps.storage, chain_position.into(), &info.sender)?;
+    HOUSE_BANKROLL.save(deps.storage, &bankroll)?;
+    HOUSE_EXPOSURE.save(deps.storage, &exposure)?;
+    save_house_game(deps.storage, &info.sender, &house_game)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "play_house")
+        .add_attribute("player", info.sender)
+        .add_attribute("game_id", house_game.id.to_string())
+        .add_attribute("bet", events::coins_to_string(&house_game.bet_amount)))
+}
+
+pub fn try_reveal_house_game(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    game_move: GameMove,
+    nonce: String,
+) -> Result<Response, ContractError> {
+    let mut house_game = HOUSE_GAMES
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::InvalidGame {})?;
+
+    // Get the hash from the player move and nonce
+    let move_hash = format!(
+        "{:x}",
+        Sha256::digest(format!("{}{}", game_move, nonce).as_bytes())
+    );
+
+    // Verify that the hashes match up, the player can only reveal once
+    match &house_game.player_move {
+        PlayerMove::HashedMove(hashed_move) if *hashed_move == move_hash => {}
+        _ => return Err(ContractError::Unauthorized {}),
+    }
+
+    house_game.player_move = PlayerMove::GameMove(game_move);
+    house_game.updated_at = env.block.time.nanos();
+    save_house_game(deps.storage, &info.sender, &house_game)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "reveal_house_game")
+        .add_attribute("player", info.sender)
+        .add_attribute("game_id", house_game.id.to_string()))
+}
+
+pub fn try_reveal_house_move(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    game_move: GameMove,
+    nonce: String,
+    previous_link: String,
+) -> Result<Response, ContractError> {
+    let config = HOUSE_CONFIG
+        .may_load(deps.storage)?
+        .ok_or(ContractError::HouseUnavailable {})?;
+    if info.sender != config.operator {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Links open in the order they were dealt, the next one is always the head
+    let mut chain = HOUSE_CHAIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::HouseUnavailable {})?;
+    if chain.revealed >= chain.dealt {
+        return Err(ContractError::InvalidGame {});
+    }
+    let position = chain.revealed;
+
+    // A game claimed before the house revealed is gone, its link still has to be opened
+    let house_game = match HOUSE_CHAIN_GAMES.may_load(deps.storage, position.into())? {
+        Some(player) => Some(HOUSE_GAMES.load(deps.storage, &player)?),
+        None => None,
+    };
+
+    // The house only reveals after the player, so it can't pick its move after seeing theirs
+    let player_move = match house_game
+        .as_ref()
+        .map(|house_game| &house_game.player_move)
+    {
+        Some(PlayerMove::GameMove(player_move)) => Some(player_move.clone()),
+        Some(_) => return Err(ContractError::Unauthorized {}),
+        None => None,
+    };
+
+    // Verify that the link opens to this move and the previous link
+    let link = format!(
+        "{:x}",
+        Sha256::digest(format!("{}{}{}", game_move, nonce, previous_link).as_bytes())
+    );
+    if link != chain.head {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    chain.head = previous_link;
+    chain.revealed += 1;
+    HOUSE_CHAIN.save(deps.storage, &chain)?;
+
+    let res = match (house_game, player_move) {
+        (Some(house_game), Some(player_move)) => settle_house_game(
+            deps.storage,
+            &house_game,
+            get_result(player_move, game_move),
+        )?,
+        // nothing left to settle, the link only moves the chain along
+        _ => Response::new(),
+    };
+
+    Ok(res
+        .add_attribute("action", "reveal_house_move")
+        .add_attribute("chain_position", position.to_string()))
+}
+
+pub fn try_claim_house_game(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    player: String,
+) -> Result<Response, ContractError> {
+    let player = deps.api.addr_validate(&player)?;
+    let house_game = HOUSE_GAMES
+        .may_load(deps.storage, &player)?
+        .ok_or(ContractError::InvalidGame {})?;
+
+    // Can only claim a game if whoever is up hasn't moved within the reveal window
+    if house_game.updated_at + HOUSE_REVEAL_WINDOW >= env.block.time.nanos() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let (game_result, walkout) = match house_game.player_move {
+        // The house didn't reveal in time and forfeits
+        PlayerMove::GameMove(_) => (GameResult::Player1Wins, Response::new()),
+        // The player never revealed and abandoned the game, a slashed bond goes to the bankroll
+        _ => (
+            GameResult::Player2Wins,
+            record_walkout(deps.storage, &house_game.player, None, true)?,
+        ),
+    };
+
+    Ok(merge_responses(
+        settle_house_game(deps.storage, &house_game, game_result)?,
+        walkout,
+    )
+    .add_attribute("action", "claim_house_game"))
+}
+
+/// Helper function for paying out a house game, the player is player 1 in the result
+fn settle_house_game(
+    storage: &mut dyn Storage,
+    house_game: &HouseGame,
+    game_result: GameResult,
+) -> StdResult<Response> {
+    remove_house_game(storage, &house_game.player)?;
+    HOUSE_CHAIN_GAMES.remove(storage, house_game.chain_position.into());
+
+    let mut exposure = sub_coins(
+        &HOUSE_EXPOSURE.may_load(storage)?.unwrap_or_default(),
+        &house_game.bet_amount,
+    )
+    .unwrap_or_default();
+    exposure.retain(|coin| !coin.amount.is_zero());
+    HOUSE_EXPOSURE.save(storage, &exposure)?;
+
+    let mut bankroll = HOUSE_BANKROLL.may_load(storage)?.unwrap_or_default();
+    let mut pot = house_game.bet_amount.clone();
+    add_coins(&mut pot, &house_game.bet_amount);
+
+    let (res, result) = match game_result {
+        GameResult::Player1Wins => (
+            Response::new().add_message(BankMsg::Send {
+                to_address: house_game.player.to_string(),
+                amount: pot,
+            }),
+            "player_wins",
+        ),
+        GameResult::Player2Wins => {
+            add_coins(&mut bankroll, &pot);
+            (Response::new(), "house_wins")
+        }
+        GameResult::Tie => {
+            add_coins(&mut bankroll, &house_game.bet_amount);
+            (
+                Response::new().add_message(BankMsg::Send {
+                    to_address: house_game.player.to_string(),
+                    amount: house_game.bet_amount.clone(),
+                }),
+                "tie",
+            )
+        }
+    };
+    HOUSE_BANKROLL.save(storage, &bankroll)?;
+
+    Ok(res
+        .add_attribute("player", house_game.player.clone())
+        .add_attribute("game_id", house_game.id.to_string())
+        .add_attribute("result", result))
+}
+
+pub fn try_add_achievement(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    achievement: Achievement,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    // Definitions can't be swapped out from under players who already unlocked them
+    if achievement.id.is_empty() || ACHIEVEMENTS.has(deps.storage, &achievement.id) {
+        return Err(ContractError::InvalidAchievement {});
+    }
+
+    ACHIEVEMENTS.save(deps.storage, &achievement.id, &achievement)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_achievement")
+        .add_attribute("achievement", achievement.id))
+}
+
+pub fn try_add_nft_collection(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collection: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let collection_addr = deps.api.addr_validate(&collection)?;
+    NFT_COLLECTIONS.save(deps.storage, &collection_addr, &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_nft_collection")
+        .add_attribute("collection", collection_addr))
+}
+
+pub fn try_remove_nft_collection(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collection: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    // Games and queued bets from the collection still play out
+    let collection_addr = deps.api.addr_validate(&collection)?;
+    NFT_COLLECTIONS.remove(deps.storage, &collection_addr);
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_nft_collection")
+        .add_attribute("collection", collection_addr))
+}
+
+/// Helper function for the achievements every contract starts out with
+fn default_achievements() -> Vec<Achievement> {
+    vec![
+        Achievement {
+            id: String::from("first_win"),
+            name: String::from("First Win"),
+            description: String::from("Win your first game"),
+            criteria: AchievementCriteria::GamesWon { count: 1 },
+        },
+        Achievement {
+            id: String::from("ten_wins"),
+            name: String::from("Veteran"),
+            description: String::from("Win 10 games"),
+            criteria: AchievementCriteria::GamesWon { count: 10 },
+        },
+        Achievement {
+            id: String::from("win_streak_5"),
+            name: String::from("On Fire"),
+            description: String::from("Win 5 games in a row"),
+            criteria: AchievementCriteria::WinStreak { length: 5 },
+        },
+        Achievement {
+            id: String::from("giant_slayer"),
+            name: String::from("Giant Slayer"),
+            description: String::from("Beat a top 10 player"),
+            criteria: AchievementCriteria::BeatTopPlayer { rank: 10 },
+        },
+        Achievement {
+            id: String::from("rock_solid"),
+            name: String::from("Rock Solid"),
+            description: String::from("Win a game playing only Rock"),
+            criteria: AchievementCriteria::SingleMoveWin {
+                game_move: GameMove::Rock,
+            },
+        },
+        Achievement {
+            id: String::from("hot_hand"),
+            name: String::from("Hot Hand"),
+            description: String::from("Win 3 hands in a row"),
+            criteria: AchievementCriteria::HandStreak { length: 3 },
+        },
+    ]
+}
+
+pub fn try_start_season(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    end_time: u64,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let now = env.block.time.nanos();
+    if end_time <= now {
+        return Err(ContractError::InvalidSeasonEndTime {});
+    }
+
+    // Only one season can run at a time, one that ran past its end time is closed here
+    let maybe_latest_season_id = LATEST_SEASON_ID.may_load(deps.storage)?;
+    if let Some(latest_season_id) = maybe_latest_season_id {
+        let latest_season = SEASONS.load(deps.storage, U32Key::new(latest_season_id))?;
+        if !latest_season.ended {
+            if latest_season.end_time > now {
+                return Err(ContractError::SeasonInProgress {});
+            }
+            SEASONS.save(
+                deps.storage,
+                U32Key::new(latest_season_id),
+                &Season {
+                    ended: true,
+                    ..latest_season
+                },
+            )?;
+        }
+    }
+
+    let season = Season {
+        id: maybe_latest_season_id.map_or(1, |id| id + 1),
+        start_time: now,
+        end_time,
+        ended: false,
+    };
+
+    SEASONS.save(deps.storage, U32Key::new(season.id), &season)?;
+    LATEST_SEASON_ID.save(deps.storage, &season.id)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "start_season")
+        .add_attribute("season_id", season.id.to_string()))
+}
+
+pub fn try_end_season(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let latest_season_id = LATEST_SEASON_ID
+        .may_load(deps.storage)?
+        .ok_or(ContractError::InvalidSeason {})?;
+    let season = SEASONS.load(deps.storage, U32Key::new(latest_season_id))?;
+
+    if season.ended {
+        return Err(ContractError::InvalidSeason {});
+    }
+
+    // Freeze the standings, no more results are recorded for this season
+    let ended_season = Season {
+        end_time: season.end_time.min(env.block.time.nanos()),
+        ended: true,
+        ..season
+    };
+    SEASONS.save(deps.storage, U32Key::new(ended_season.id), &ended_season)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "end_season")
+        .add_attribute("season_id", ended_season.id.to_string()))
+}
+
+pub fn try_fund_prize_pool(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if info.funds.is_empty() {
+        return Err(ContractError::IncorrectFunds {});
+    }
+
+    let mut prize_pool = PRIZE_POOL.may_load(deps.storage)?.unwrap_or_default();
+    add_coins(&mut prize_pool, &info.funds);
+    PRIZE_POOL.save(deps.storage, &prize_pool)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_prize_pool")
+        .add_attribute("funder", info.sender))
+}
+
+pub fn try_set_payout_curve(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    payout_curve: Vec<u8>,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let total_percentage: u32 = payout_curve.iter().map(|p| *p as u32).sum();
+    if payout_curve.is_empty() || total_percentage > 100 {
+        return Err(ContractError::InvalidPayoutCurve {});
+    }
+
+    PAYOUT_CURVE.save(deps.storage, &payout_curve)?;
+
+    Ok(Response::new().add_attribute("action", "set_payout_curve"))
+}
+
+pub fn try_set_side_bet_cap(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    cap: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    SIDE_BET_CAP.save(deps.storage, &cap)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_side_bet_cap")
+        .add_attribute("cap", events::coins_to_string(&cap)))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn try_set_fee_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    fee_percentage: u8,
+    distribution_contract: Option<String>,
+    distribution_percentage: u8,
+    referral_percentage: u8,
+    prize_pool_percentage: u8,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let distribution_contract = maybe_addr(deps.api, distribution_contract)?;
+    if fee_percentage > MAX_FEE_PERCENTAGE
+        || distribution_percentage > 100
+        || referral_percentage > 100
+        || prize_pool_percentage > 100
+        || (distribution_percentage > 0 && distribution_contract.is_none())
+    {
+        return Err(ContractError::InvalidFeeConfig {});
+    }
+
+    FEE_CONFIG.save(
+        deps.storage,
+        &FeeConfig {
+            fee_percentage,
+            distribution_contract,
+            distribution_percentage,
+            referral_percentage,
+            prize_pool_percentage,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_fee_config")
+        .add_attribute("fee_percentage", fee_percentage.to_string())
+        .add_attribute(
+            "distribution_percentage",
+            distribution_percentage.to_string(),
+        )
+        .add_attribute("referral_percentage", referral_percentage.to_string())
+        .add_attribute("prize_pool_percentage", prize_pool_percentage.to_string()))
+}
+
+pub fn try_place_side_bet(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    player1: String,
+    player2: String,
+    on: BetSide,
+) -> Result<Response, ContractError> {
+    // Validators. Can only place a side bet if:
+    // - you aren't playing in the game
+    // - no move of the game has been revealed yet
+    // - you haven't bet on the game already
+    // - the bet keeps the game's side bets within the cap
+
+    let game_state = game_states()
+        .may_load(deps.storage, (player1.as_bytes(), player2.as_bytes()))?
+        .ok_or(ContractError::InvalidGame {})?;
+
+    if info.sender == game_state.player1 || info.sender == game_state.player2 {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let amount = match &info.funds[..] {
+        [amount] if !amount.amount.is_zero() => amount.clone(),
+        _ => return Err(ContractError::IncorrectFunds {}),
+    };
+
+    // Channel games are scored off-chain and games from before ids can't be told apart
+    let revealed = |player_move: &Option<PlayerMove>| {
+        matches!(
+            player_move,
+            Some(PlayerMove::GameMove(_)) | Some(PlayerMove::GameMoves(_))
+        )
+    };
+    if game_state.mode == GameMode::Channel
+        || game_state.id == 0
+        || game_state.player1_hands_won + game_state.player2_hands_won + game_state.hands_tied > 0
+        || revealed(&game_state.player1_move)
+        || revealed(&game_state.player2_move)
+        || SIDE_BETS.has(deps.storage, (game_state.id.into(), &info.sender))
+    {
+        return Err(ContractError::SideBetsClosed {});
+    }
+
+    let cap = SIDE_BET_CAP
+        .may_load(deps.storage)?
+        .ok_or(ContractError::SideBetsClosed {})?;
+    let mut pool = SIDE_BET_POOLS
+        .may_load(deps.storage, game_state.id.into())?
+        .unwrap_or_default();
+    match on {
+        BetSide::Player1 => add_coins(&mut pool.player1, &info.funds),
+        BetSide::Player2 => add_coins(&mut pool.player2, &info.funds),
+    }
+    let mut total = pool.player1.clone();
+    add_coins(&mut total, &pool.player2);
+    if sub_coins(&cap, &total).is_none() {
+        return Err(ContractError::SideBetCapExceeded {});
+    }
+
+    save_side_bet_pool(deps.storage, game_state.id.into(), &pool)?;
+    SIDE_BETS.save(
+        deps.storage,
+        (game_state.id.into(), &info.sender),
+        &SideBet {
+            bettor: info.sender.clone(),
+            on,
+            amount: amount.clone(),
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "place_side_bet")
+        .add_attribute("players", format!("{},{}", player1, player2))
+        .add_attribute("bettor", info.sender)
+        .add_attribute("amount", events::coins_to_string(&[amount])))
+}
+
+pub fn try_post_penalty_bond(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if info.funds.is_empty() || info.funds.iter().any(|coin| coin.amount.is_zero()) {
+        return Err(ContractError::IncorrectFunds {});
+    }
+
+    let mut bond = PENALTY_BONDS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    add_coins(&mut bond, &info.funds);
+    save_penalty_bond(deps.storage, &info.sender, &bond)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "post_penalty_bond")
+        .add_attribute("player", info.sender)
+        .add_attribute("penalty_bond", events::coins_to_string(&bond)))
+}
+
+pub fn try_withdraw_penalty_bond(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // The bond has to stay put while it could still be slashed
+    if player_is_busy(deps.storage, &info.sender)? {
+        return Err(ContractError::PlayerBusy {});
+    }
+
+    let bond = PENALTY_BONDS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::IncorrectFunds {})?;
+    remove_penalty_bond(deps.storage, &info.sender)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: bond.clone(),
+        })
+        .add_attribute("action", "withdraw_penalty_bond")
+        .add_attribute("player", info.sender)
+        .add_attribute("penalty_bond", events::coins_to_string(&bond)))
+}
+
+pub fn try_claim_referral_rewards(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut stats = REFERRAL_STATS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if stats.balance.is_empty() {
+        return Err(ContractError::NoReferralRewards {});
+    }
+
+    let rewards = std::mem::take(&mut stats.balance);
+    save_referral_stats(deps.storage, &info.sender, &stats)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: rewards.clone(),
+        })
+        .add_attribute("action", "claim_referral_rewards")
+        .add_attribute("referrer", info.sender)
+        .add_attribute("amount", events::coins_to_string(&rewards)))
+}
+
+pub fn try_withdraw_fees(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    to: String,
+    denom: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let to = deps.api.addr_validate(&to)?;
+    let accrued = ACCRUED_FEES
+        .may_load(deps.storage, &denom)?
+        .unwrap_or_default();
+    if amount.is_zero() || amount > accrued {
+        return Err(ContractError::InsufficientFees {});
+    }
+    ACCRUED_FEES.save(deps.storage, &denom, &(accrued - amount))?;
+
+    // Split off the distribution contract's share first
+    let mut res = Response::new();
+    let mut remaining = amount;
+    if let Some(FeeConfig {
+        distribution_contract: Some(distribution_contract),
+        distribution_percentage,
+        ..
+    }) = FEE_CONFIG.may_load(deps.storage)?
+    {
+        let share = amount.multiply_ratio(distribution_percentage as u128, 100u128);
+        if !share.is_zero() {
+            remaining -= share;
+            res = res
+                .add_message(BankMsg::Send {
+                    to_address: distribution_contract.to_string(),
+                    amount: vec![Coin {
+                        denom: denom.clone(),
+                        amount: share,
+                    }],
+                })
+                .add_attribute("distributed", share.to_string());
+        }
+    }
+    if !remaining.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: vec![Coin {
+                denom: denom.clone(),
+                amount: remaining,
+            }],
+        });
+    }
+
+    Ok(res
+        .add_attribute("action", "withdraw_fees")
+        .add_attribute("to", to)
+        .add_attribute("amount", format!("{}{}", amount, denom)))
+}
+
+pub fn try_distribute_prizes(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    season_id: u32,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let season = SEASONS
+        .may_load(deps.storage, U32Key::new(season_id))?
+        .ok_or(ContractError::InvalidSeason {})?;
+    if !season.ended {
+        // A season past its end time is over even without an EndSeason
+        if season.end_time > env.block.time.nanos() {
+            return Err(ContractError::SeasonNotEnded {});
+        }
+        SEASONS.save(
+            deps.storage,
+            U32Key::new(season_id),
+            &Season {
+                ended: true,
+                ..season
+            },
+        )?;
+    }
+
+    // The first batch locks in the prize pool and payout curve for the season
+    let mut distribution =
+        match PRIZE_DISTRIBUTIONS.may_load(deps.storage, U32Key::new(season_id))? {
+            Some(distribution) => distribution,
+            None => {
+                let payout_curve = PAYOUT_CURVE.may_load(deps.storage)?.unwrap_or_default();
+                if payout_curve.is_empty() {
+                    return Err(ContractError::InvalidPayoutCurve {});
+                }
+
+                let prize_pool = PRIZE_POOL.may_load(deps.storage)?.unwrap_or_default();
+                PRIZE_POOL.save(deps.storage, &vec![])?;
+
+                PrizeDistribution {
+                    season_id,
+                    prize_pool,
+                    payout_curve,
+                    num_paid: 0,
+                    last_paid_key: None,
+                    complete: false,
+                }
+            }
+        };
+
+    if distribution.complete {
+        return Err(ContractError::PrizesAlreadyDistributed {});
+    }
+
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let batch_size = limit.min(distribution.payout_curve.len() - distribution.num_paid as usize);
+
+    // Walk the season leaderboard from where the previous batch stopped
+    let zero_winnings_key = (I32Key::from(0), b"".to_vec()).joined_key();
+    let most_negative_key = (I32Key::from(-2147483648), b"".to_vec()).joined_key();
+    let max_key = distribution
+        .last_paid_key
+        .clone()
+        .unwrap_or(most_negative_key);
+
+    let winners = season_leaderboard()
+        .idx
+        .winnings
+        .sub_prefix(U32Key::new(season_id))
+        .range(
+            deps.storage,
+            Some(Bound::inclusive(zero_winnings_key)),
+            Some(Bound::exclusive(max_key)),
+            Order::Descending,
+        )
+        .take(batch_size)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut messages = vec![];
+    let mut trophy = vec![];
+    for (pk, season_profile) in winners.iter() {
+        let percentage = distribution.payout_curve[distribution.num_paid as usize];
+        let prize = coins_percentage(&distribution.prize_pool, percentage);
+        if !prize.is_empty() {
+            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: season_profile.profile.address.clone().into(),
+                amount: prize,
+            }));
+        }
+
+        // The season champion gets a trophy along with the first place prize
+        if distribution.num_paid == 0 {
+            let profile = &season_profile.profile;
+            trophy = mint_trophy(
+                deps.storage,
+                &env,
+                format!("season-{}", season_id),
+                &profile.address,
+                format!("Season #{} Champion", season_id),
+                String::from("Finished a season top of the leaderboard"),
+                vec![
+                    trait_attribute(
+                        "record",
+                        format!(
+                            "{}-{}-{}",
+                            profile.num_games_won,
+                            profile.num_games_played
+                                - profile.num_games_won
+                                - profile.num_games_drawn,
+                            profile.num_games_drawn
+                        ),
+                    ),
+                    trait_attribute("winnings", profile.winnings.to_string()),
+                ],
+            )?;
+        }
+
+        distribution.num_paid += 1;
+        distribution.last_paid_key =
+            Some((I32Key::from(season_profile.profile.winnings), pk.clone()).joined_key());
+    }
+
+    // Done once every rank is paid or we ran out of players
+    distribution.complete = distribution.num_paid as usize == distribution.payout_curve.len()
+        || winners.len() < batch_size;
+
+    if distribution.complete {
+        // Whatever wasn't paid out goes back into the pool for the next season
+        let mut prize_pool = PRIZE_POOL.may_load(deps.storage)?.unwrap_or_default();
+        add_coins(&mut prize_pool, &prizes_remaining(&distribution));
+        PRIZE_POOL.save(deps.storage, &prize_pool)?;
+    }
+
+    save_prize_distribution(deps.storage, season_id, &distribution)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_submessages(trophy)
+        .add_attribute("action", "distribute_prizes")
+        .add_attribute("season_id", season_id.to_string())
+        .add_attribute("num_paid", distribution.num_paid.to_string())
+        .add_attribute("complete", distribution.complete.to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn try_create_tournament(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    size: u32,
+    buy_in: Vec<Coin>,
+    num_hands_to_win: u8,
+    registration_deadline: u64,
+    payout_curve: Option<Vec<u8>>,
+) -> Result<Response, ContractError> {
+    // Single elimination needs a full bracket
+    if !(2..=64).contains(&size) || !size.is_power_of_two() {
+        return Err(ContractError::InvalidTournamentSize {});
+    }
+
+    if registration_deadline <= env.block.time.nanos() {
+        return Err(ContractError::TournamentRegistrationClosed {});
+    }
+
+    // By default the champion takes it all
+    let payout_curve = payout_curve.unwrap_or_else(|| vec![100]);
+    let num_rounds = size.trailing_zeros() as usize;
+    let total_percentage: u32 = payout_curve.iter().map(|p| *p as u32).sum();
+    if payout_curve.is_empty() || payout_curve.len() > num_rounds + 1 || total_percentage > 100 {
+        return Err(ContractError::InvalidPayoutCurve {});
+    }
+
+    // The prize pool has to fit once the bracket is full
+    multiply_coins(&buy_in, size as u128)?;
+
+    let tournament = Tournament {
+        id: LATEST_TOURNAMENT_ID
+            .may_load(deps.storage)?
+            .map_or(1, |id| id + 1),
+        creator: info.sender,
+        size,
+        buy_in,
+        num_hands_to_win,
+        registration_deadline,
+        payout_curve,
+        players: vec![],
+        bracket: vec![],
+        status: TournamentStatus::Registration,
+    };
+
+    save_tournament(deps.storage, U32Key::new(tournament.id), &tournament)?;
+    LATEST_TOURNAMENT_ID.save(deps.storage, &tournament.id)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_tournament")
+        .add_attribute("tournament_id", tournament.id.to_string()))
+}
+
+pub fn try_register_for_tournament(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    tournament_id: u32,
+) -> Result<Response, ContractError> {
+    let mut tournament = TOURNAMENTS
+        .may_load(deps.storage, U32Key::new(tournament_id))?
+        .ok_or(ContractError::InvalidTournament {})?;
+
+    if tournament.status != TournamentStatus::Registration
+        || env.block.time.nanos() > tournament.registration_deadline
+    {
+        return Err(ContractError::TournamentRegistrationClosed {});
+    }
+
+    if info.funds != tournament.buy_in {
+        return Err(ContractError::IncorrectFunds {});
+    }
+
+    // Bracket games can't be created for players who are busy elsewhere
+    if player_is_busy(deps.storage, &info.sender)? {
+        return Err(ContractError::PlayerBusy {});
+    }
+
+    tournament.players.push(info.sender.clone());
+    TOURNAMENT_PLAYERS.save(deps.storage, &info.sender, &tournament_id)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "register_for_tournament")
+        .add_attribute("tournament_id", tournament_id.to_string())
+        .add_attribute("player", info.sender);
+
+    if tournament.players.len() as u32 == tournament.size {
+        // Seed the bracket in registration order and start the first round
+        let num_rounds = tournament.size.trailing_zeros();
+        tournament.bracket = (0..=num_rounds)
+            .map(|round| {
+                if round == 0 {
+                    tournament.players.iter().cloned().map(Some).collect()
+                } else {
+                    vec![None; (tournament.size >> round) as usize]
+                }
+            })
+            .collect();
+
+        for players in tournament.players.chunks(2) {
+            res = merge_responses(
+                res,
+                start_tournament_game(deps.storage, &env, &tournament, &players[0], &players[1])?,
+            );
+        }
+
+        tournament.status = TournamentStatus::InProgress;
+        res = res.add_attribute("tournament_started", "true");
+    }
+
+    save_tournament(deps.storage, U32Key::new(tournament_id), &tournament)?;
+
+    Ok(res)
+}
+
+pub fn try_cancel_tournament(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    tournament_id: u32,
+) -> Result<Response, ContractError> {
+    let tournament = TOURNAMENTS
+        .may_load(deps.storage, U32Key::new(tournament_id))?
+        .ok_or(ContractError::InvalidTournament {})?;
+
+    let mut side_bet_refunds = vec![];
+    let mut walkouts = Response::new();
+    match tournament.status {
+        TournamentStatus::Registration => {
+            // The creator can cancel any time, anyone else once registration didn't fill up in time
+            if info.sender != tournament.creator
+                && env.block.time.nanos() <= tournament.registration_deadline
+            {
+                return Err(ContractError::Unauthorized {});
+            }
+        }
+        TournamentStatus::InProgress => {
+            // Anyone can cancel once both players of a game have stayed away long enough,
+            // they abandoned it. The games still being played are called off
+            let games = tournament_games(deps.storage, &tournament)?;
+            let stalled: Vec<&GameState> = games
+                .iter()
+                .filter(|game_state| {
+                    game_state.player1_move.is_none()
+                        && game_state.player2_move.is_none()
+                        && game_state.updated_at + TOURNAMENT_STALL_WINDOW < env.block.time.nanos()
+                })
+                .collect();
+            if stalled.is_empty() {
+                return Err(ContractError::TournamentInProgress {});
+            }
+
+            for game_state in stalled {
+                for (player, opponent) in [
+                    (&game_state.player1, &game_state.player2),
+                    (&game_state.player2, &game_state.player1),
+                ] {
+                    walkouts = merge_responses(
+                        walkouts,
+                        record_walkout(deps.storage, player, Some(opponent), true)?,
+                    );
+                }
+            }
+            for game_state in games.iter() {
+                side_bet_refunds.extend(settle_side_bets(deps.storage, game_state.id, None)?);
+                remove_game_state(
+                    deps.storage,
+                    (game_state.player1.as_bytes(), game_state.player2.as_bytes()),
+                )?;
+            }
+        }
+        _ => return Err(ContractError::TournamentRegistrationClosed {}),
+    }
+
+    // Refund everyone who registered
+    let mut messages = vec![];
+    for player in tournament.players.iter() {
+        TOURNAMENT_PLAYERS.remove(deps.storage, player);
+        if !tournament.buy_in.is_empty() {
+            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: player.clone().into(),
+                amount: tournament.buy_in.clone(),
+            }));
+        }
+    }
+
+    save_tournament(
+        deps.storage,
+        U32Key::new(tournament_id),
+        &Tournament {
+            status: TournamentStatus::Cancelled,
+            ..tournament
+        },
+    )?;
+
+    Ok(walkouts
+        .add_messages(messages)
+        .add_messages(side_bet_refunds)
+        .add_attribute("action", "cancel_tournament")
+        .add_attribute("tournament_id", tournament_id.to_string()))
+}
+
+/// Helper function for checking if a player is in a game, the waiting queue, a tournament or a league
+fn player_is_busy(storage: &dyn Storage, player: &Addr) -> StdResult<bool> {
+    if game_states()
+        .idx
+        .player1
+        .item(storage, player.clone())?
+        .is_some()
+        || game_states()
+            .idx
+            .player2
+            .item(storage, player.clone())?
+            .is_some()
+        || TOURNAMENT_PLAYERS.has(storage, player)
+        || LEAGUE_PLAYERS.has(storage, player)
+    {
+        return Ok(true);
+    }
+
+    let query_res = UNMATCHED_PLAYERS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(query_res.iter().any(|(_, b)| &b.address == player))
+}
+
+/// Helper function for the bracket games of a tournament still being played
+fn tournament_games(storage: &dyn Storage, tournament: &Tournament) -> StdResult<Vec<GameState>> {
+    let mut games = vec![];
+    for (round, players) in tournament.bracket.iter().enumerate().skip(1) {
+        for (position, winner) in players.iter().enumerate() {
+            if let (None, Some(player1), Some(player2)) = (
+                winner,
+                &tournament.bracket[round - 1][2 * position],
+                &tournament.bracket[round - 1][2 * position + 1],
+            ) {
+                if let Some(game_state) =
+                    game_states().may_load(storage, (player1.as_bytes(), player2.as_bytes()))?
+                {
+                    games.push(game_state);
+                }
+            }
+        }
+    }
+    Ok(games)
+}
+
+/// Helper function for starting a bracket game, the stakes are already held by the tournament
+fn start_tournament_game(
+    storage: &mut dyn Storage,
+    env: &Env,
+    tournament: &Tournament,
+    player1: &Addr,
+    player2: &Addr,
+) -> StdResult<Response> {
+    let mut game_state = GameState {
+        tournament_id: Some(tournament.id),
+        ..new_game_state(
+            env,
+            player1.clone(),
+            player2.clone(),
+            Asset::Coins(vec![]),
+            tournament.num_hands_to_win,
+        )
+    };
+
+    save_new_game(storage, &mut game_state)
+}
+
+/// Helper function for moving the winner of a bracket game on to their next match
+fn advance_tournament(
+    deps: DepsMut,
+    env: Env,
+    tournament_id: u32,
+    winner: Addr,
+    loser: Addr,
+) -> Result<Response, ContractError> {
+    let mut tournament = TOURNAMENTS.load(deps.storage, U32Key::new(tournament_id))?;
+
+    // The game that just finished is the latest round the winner appears in
+    let round = tournament
+        .bracket
+        .iter()
+        .rposition(|players| players.contains(&Some(winner.clone())))
+        .ok_or(ContractError::InvalidTournament {})?;
+    let position = tournament.bracket[round]
+        .iter()
+        .position(|player| player.as_ref() == Some(&winner))
+        .ok_or(ContractError::InvalidTournament {})?;
+
+    let next_position = position / 2;
+    tournament.bracket[round + 1][next_position] = Some(winner.clone());
+
+    // The loser is out of the tournament
+    TOURNAMENT_PLAYERS.remove(deps.storage, &loser);
+
+    let res = if round + 2 == tournament.bracket.len() {
+        // That was the final
+        TOURNAMENT_PLAYERS.remove(deps.storage, &winner);
+        tournament.status = TournamentStatus::Complete;
+
+        let num_rounds = tournament.bracket.len() - 1;
+        let trophy = mint_trophy(
+            deps.storage,
+            &env,
+            format!("tournament-{}", tournament_id),
+            &winner,
+            format!("Tournament #{} Champion", tournament_id),
+            format!("Won a {} player tournament", tournament.size),
+            vec![
+                trait_attribute("record", format!("{}-0", num_rounds)),
+                trait_attribute("buy_in", events::coins_to_string(&tournament.buy_in)),
+            ],
+        )?;
+
+        Response::new()
+            .add_messages(tournament_payouts(&tournament)?)
+            .add_submessages(trophy)
+            .add_attribute("tournament_champion", winner)
+    } else if let Some(opponent) = tournament.bracket[round + 1][next_position ^ 1].clone() {
+        // Both sides of the next match are decided, so start it
+        let (player1, player2) = if next_position % 2 == 0 {
+            (winner, opponent)
+        } else {
+            (opponent, winner)
+        };
+        start_tournament_game(deps.storage, &env, &tournament, &player1, &player2)?.add_attribute(
+            "tournament_game_started",
+            format!("{},{}", player1, player2),
+        )
+    } else {
+        // Wait for the other match feeding into the next round
+        Response::new()
+    };
+
+    save_tournament(deps.storage, U32Key::new(tournament_id), &tournament)?;
+
+    Ok(res.add_attribute("tournament_id", tournament_id.to_string()))
+}
+
+/// Helper function for splitting a finished tournament's prize pool by placement
+fn tournament_payouts(tournament: &Tournament) -> StdResult<Vec<CosmosMsg>> {
+    let num_rounds = tournament.bracket.len() - 1;
+    let prize_pool = multiply_coins(&tournament.buy_in, tournament.size as u128)?;
+
+    // The champion places first, then the losers of each round counting back from the final
+    let mut payouts: Vec<(Addr, Vec<Coin>)> = vec![];
+    for (placement, percentage) in tournament.payout_curve.iter().enumerate() {
+        let round = num_rounds - placement;
+        let placed: Vec<Addr> = tournament.bracket[round]
+            .iter()
+            .flatten()
+            .filter(|player| {
+                placement == 0 || !tournament.bracket[round + 1].contains(&Some((*player).clone()))
+            })
+            .cloned()
+            .collect();
+
+        for player in placed.iter() {
+            let prize = prize_pool
+                .iter()
+                .map(|coin| Coin {
+                    denom: coin.denom.clone(),
+                    amount: coin
+                        .amount
+                        .multiply_ratio(*percentage as u128, 100 * placed.len() as u128),
+                })
+                .collect();
+            payouts.push((player.clone(), prize));
+        }
+    }
+
+    // Rounding dust and any unallocated share goes to the champion
+    let remainder: Vec<Coin> = prize_pool
+        .iter()
+        .map(|coin| {
+            let amount_paid = payouts
+                .iter()
+                .flat_map(|(_, prize)| prize.iter())
+                .filter(|prize_coin| prize_coin.denom == coin.denom)
+                .fold(Uint128::zero(), |total, prize_coin| {
+                    total + prize_coin.amount
+                });
+            Coin {
+                denom: coin.denom.clone(),
+                amount: coin.amount - amount_paid,
+            }
+        })
+        .collect();
+    add_coins(&mut payouts[0].1, &remainder);
+
+    Ok(payouts
+        .into_iter()
+        .map(|(player, prize)| {
+            (
+                player,
+                prize
+                    .into_iter()
+                    .filter(|coin| !coin.amount.is_zero())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .filter(|(_, prize)| !prize.is_empty())
+        .map(|(player, prize)| {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: player.into(),
+                amount: prize,
+            })
+        })
+        .collect())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn try_create_league(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    participants: Vec<String>,
+    entry_fee: Vec<Coin>,
+    num_hands_to_win: u8,
+    join_deadline: u64,
+    round_duration: u64,
+) -> Result<Response, ContractError> {
+    let participants = participants
+        .iter()
+        .map(|participant| deps.api.addr_validate(participant))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // Everyone plays everyone once, so keep the number of fixtures bounded
+    let mut distinct_participants = participants.clone();
+    distinct_participants.sort();
+    distinct_participants.dedup();
+    if !(2..=16).contains(&participants.len()) || distinct_participants.len() != participants.len()
+    {
+        return Err(ContractError::InvalidLeagueParticipants {});
+    }
+
+    if join_deadline <= env.block.time.nanos() {
+        return Err(ContractError::LeagueRegistrationClosed {});
+    }
+
+    // The prize pool has to fit once everyone has paid in
+    multiply_coins(&entry_fee, participants.len() as u128)?;
+
+    let league = League {
+        id: LATEST_LEAGUE_ID
+            .may_load(deps.storage)?
+            .map_or(1, |id| id + 1),
+        creator: info.sender,
+        participants,
+        paid: vec![],
+        entry_fee,
+        num_hands_to_win,
+        join_deadline,
+        round_duration,
+        schedule: vec![],
+        current_round: 0,
+        round_started_at: 0,
+        standings: vec![],
+        status: LeagueStatus::Registration,
+    };
+
+    save_league(deps.storage, U32Key::new(league.id), &league)?;
+    LATEST_LEAGUE_ID.save(deps.storage, &league.id)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_league")
+        .add_attribute("league_id", league.id.to_string()))
+}
+
+pub fn try_join_league(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    league_id: u32,
+) -> Result<Response, ContractError> {
+    let mut league = LEAGUES
+        .may_load(deps.storage, U32Key::new(league_id))?
+        .ok_or(ContractError::InvalidLeague {})?;
+
+    if league.status != LeagueStatus::Registration || env.block.time.nanos() > league.join_deadline
+    {
+        return Err(ContractError::LeagueRegistrationClosed {});
+    }
+
+    // Only the invited participants can join
+    if !league.participants.contains(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if info.funds != league.entry_fee {
+        return Err(ContractError::IncorrectFunds {});
+    }
+
+    // League games can't be created for players who are busy elsewhere
+    if player_is_busy(deps.storage, &info.sender)? {
+        return Err(ContractError::PlayerBusy {});
+    }
+
+    league.paid.push(info.sender.clone());
+    LEAGUE_PLAYERS.save(deps.storage, &info.sender, &league_id)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "join_league")
+        .add_attribute("league_id", league_id.to_string())
+        .add_attribute("player", info.sender);
+
+    if league.paid.len() == league.participants.len() {
+        // Everyone is in, schedule the fixtures and start the first round
+        league.schedule = round_robin_schedule(&league.participants);
+        league.standings = league
+            .participants
+            .iter()
+            .map(|participant| LeagueStanding {
+                player: participant.clone(),
+                points: 0,
+                played: 0,
+                won: 0,
+                tied: 0,
+                lost: 0,
+            })
+            .collect();
+        league.status = LeagueStatus::InProgress;
+        res = merge_responses(res, start_league_round(deps.storage, &env, &mut league)?)
+            .add_attribute("league_started", "true");
+    }
+
+    save_league(deps.storage, U32Key::new(league_id), &league)?;
+
+    Ok(res)
+}
+
+pub fn try_cancel_league(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    league_id: u32,
+) -> Result<Response, ContractError> {
+    let league = LEAGUES
+        .may_load(deps.storage, U32Key::new(league_id))?
+        .ok_or(ContractError::InvalidLeague {})?;
+
+    if league.status != LeagueStatus::Registration {
+        return Err(ContractError::LeagueRegistrationClosed {});
+    }
+
+    // The creator can cancel any time, anyone else once not everyone joined in time
+    if info.sender != league.creator && env.block.time.nanos() <= league.join_deadline {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Refund everyone who paid
+    let mut messages = vec![];
+    for player in league.paid.iter() {
+        LEAGUE_PLAYERS.remove(deps.storage, player);
+        if !league.entry_fee.is_empty() {
+            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: player.clone().into(),
+                amount: league.entry_fee.clone(),
+            }));
+        }
+    }
+
+    save_league(
+        deps.storage,
+        U32Key::new(league_id),
+        &League {
+            status: LeagueStatus::Cancelled,
+            ..league
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "cancel_league")
+        .add_attribute("league_id", league_id.to_string()))
+}
+
+pub fn try_timeout_league_round(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    league_id: u32,
+) -> Result<Response, ContractError> {
+    let mut league = LEAGUES
+        .may_load(deps.storage, U32Key::new(league_id))?
+        .ok_or(ContractError::InvalidLeague {})?;
+
+    if league.status != LeagueStatus::InProgress {
+        return Err(ContractError::InvalidLeague {});
+    }
+
+    if env.block.time.nanos() <= league.round_started_at + league.round_duration {
+        return Err(ContractError::LeagueRoundInProgress {});
+    }
+
+    // Fixtures that haven't finished by now score nothing, and their side bets are refunded.
+    // Whoever held the game up abandoned it, or both players if neither was waiting on the other
+    let round = league.current_round as usize;
+    let mut side_bet_refunds = vec![];
+    let mut walkouts = Response::new();
+    for fixture in league.schedule[round].iter_mut() {
+        if fixture.result.is_none() {
+            let key = (fixture.player1.as_bytes(), fixture.player2.as_bytes());
+            if let Some(game_state) = game_states().may_load(deps.storage, key)? {
+                side_bet_refunds.extend(settle_side_bets(deps.storage, game_state.id, None)?);
+
+                let stalled = match stalled_by(&game_state) {
+                    Some(stalled) => vec![stalled],
+                    None => vec![
+                        (&game_state.player1, &game_state.player2),
+                        (&game_state.player2, &game_state.player1),
+                    ],
+                };
+                for (player, opponent) in stalled {
+                    walkouts = merge_responses(
+                        walkouts,
+                        record_walkout(deps.storage, player, Some(opponent), true)?,
+                    );
+                }
+            }
+            remove_game_state(deps.storage, key)?;
+            fixture.timed_out = true;
+        }
+    }
+
+    let res = merge_responses(
+        advance_league_round(deps.storage, &env, &mut league)?.add_messages(side_bet_refunds),
+        walkouts,
+    );
+    save_league(deps.storage, U32Key::new(league_id), &league)?;
+
+    Ok(res
+        .add_attribute("action", "timeout_league_round")
+        .add_attribute("league_id", league_id.to_string()))
+}
+
+/// Helper function for pairing up every participant once using the circle method
+fn round_robin_schedule(participants: &[Addr]) -> Vec<Vec<LeagueFixture>> {
+    // An odd number of participants means someone sits out each round
+    let mut slots: Vec<Option<Addr>> = participants.iter().cloned().map(Some).collect();
+    if slots.len() % 2 == 1 {
+        slots.push(None);
+    }
+    let num_slots = slots.len();
+
+    let mut schedule = vec![];
+    for _ in 0..num_slots - 1 {
+        let round = (0..num_slots / 2)
+            .filter_map(|i| match (&slots[i], &slots[num_slots - 1 - i]) {
+                (Some(player1), Some(player2)) => Some(LeagueFixture {
+                    player1: player1.clone(),
+                    player2: player2.clone(),
+                    result: None,
+                    timed_out: false,
+                }),
+                _ => None,
+            })
+            .collect();
+        schedule.push(round);
+
+        // Keep the first slot fixed and rotate everyone else
+        if let Some(last) = slots.pop() {
+            slots.insert(1, last);
+        }
+    }
+
+    schedule
+}
+
+/// Helper function for creating the games of the league's current round
+fn start_league_round(
+    storage: &mut dyn Storage,
+    env: &Env,
+    league: &mut League,
+) -> StdResult<Response> {
+    league.round_started_at = env.block.time.nanos();
+
+    let mut res = Response::new();
+    for fixture in league.schedule[league.current_round as usize].iter() {
+        let mut game_state = GameState {
+            league_id: Some(league.id),
+            tie_break: Some(TieBreak {
+                max_ties: LEAGUE_MAX_TIES,
+                policy: TiePolicy::SplitPot,
+            }),
+            ..new_game_state(
+                env,
+                fixture.player1.clone(),
+                fixture.player2.clone(),
+                Asset::Coins(vec![]),
+                league.num_hands_to_win,
+            )
+        };
+
+        res = merge_responses(res, save_new_game(storage, &mut game_state)?);
+    }
+
+    Ok(res)
+}
+
+/// Helper function for recording a finished league game
+fn advance_league(
+    storage: &mut dyn Storage,
+    env: &Env,
+    league_id: u32,
+    game_state: &GameState,
+    game_result: GameResult,
+) -> Result<Response, ContractError> {
+    let mut league = LEAGUES.load(storage, U32Key::new(league_id))?;
+
+    let round = league.current_round as usize;
+    let fixture = league.schedule[round]
+        .iter_mut()
+        .find(|fixture| {
+            fixture.player1 == game_state.player1 && fixture.player2 == game_state.player2
+        })
+        .ok_or(ContractError::InvalidLeague {})?;
+    fixture.result = Some(game_result);
+    let fixture = fixture.clone();
+
+    update_league_standings(&mut league.standings, &fixture);
+
+    let res = advance_league_round(storage, env, &mut league)?;
+    save_league(storage, U32Key::new(league_id), &league)?;
+
+    Ok(res.add_attribute("league_id", league_id.to_string()))
+}
+
+/// Helper function for awarding league points, 3 for a win and 1 each for a tied series
+fn update_league_standings(standings: &mut [LeagueStanding], fixture: &LeagueFixture) {
+    for standing in standings.iter_mut() {
+        let is_player1 = standing.player == fixture.player1;
+        if !is_player1 && standing.player != fixture.player2 {
+            continue;
+        }
+
+        match (&fixture.result, is_player1) {
+            (Some(GameResult::Tie), _) => {
+                standing.played += 1;
+                standing.tied += 1;
+                standing.points += 1;
+            }
+            (Some(GameResult::Player1Wins), true) | (Some(GameResult::Player2Wins), false) => {
+                standing.played += 1;
+                standing.won += 1;
+                standing.points += 3;
+            }
+            (Some(_), _) => {
+                standing.played += 1;
+                standing.lost += 1;
+            }
+            // Timed out fixtures don't score
+            (None, _) => {}
+        }
+    }
+}
+
+/// Helper function for starting the next round, or settling the league after the last one
+fn advance_league_round(
+    storage: &mut dyn Storage,
+    env: &Env,
+    league: &mut League,
+) -> StdResult<Response> {
+    let round = league.current_round as usize;
+    let round_complete = league.schedule[round]
+        .iter()
+        .all(|fixture| fixture.result.is_some() || fixture.timed_out);
+
+    if !round_complete {
+        return Ok(Response::new());
+    }
+
+    if round + 1 < league.schedule.len() {
+        league.current_round += 1;
+        return Ok(start_league_round(storage, env, league)?
+            .add_attribute("league_round_started", league.current_round.to_string()));
+    }
+
+    // Every fixture is decided, so pay out and free up the players
+    for participant in league.participants.iter() {
+        LEAGUE_PLAYERS.remove(storage, participant);
+    }
+    league.status = LeagueStatus::Complete;
+
+    Ok(Response::new()
+        .add_messages(league_payouts(league)?)
+        .add_attribute("league_complete", "true"))
+}
+
+/// Helper function for splitting a finished league's entry fees between the players on top
+fn league_payouts(league: &League) -> StdResult<Vec<CosmosMsg>> {
+    let top_points = league
+        .standings
+        .iter()
+        .map(|standing| standing.points)
+        .max()
+        .unwrap_or(0);
+    let leaders: Vec<&Addr> = league
+        .standings
+        .iter()
+        .filter(|standing| standing.points == top_points)
+        .map(|standing| &standing.player)
+        .collect();
+
+    let prize_pool = multiply_coins(&league.entry_fee, league.participants.len() as u128)?;
+    let num_leaders = leaders.len() as u128;
+
+    Ok(leaders
+        .iter()
+        .enumerate()
+        .map(|(i, leader)| {
+            let prize: Vec<Coin> = prize_pool
+                .iter()
+                .map(|coin| {
+                    // Integer split, the first leader also gets the remainder
+                    let prize_pool = coin.amount.u128();
+                    let remainder = if i == 0 { prize_pool % num_leaders } else { 0 };
+                    Coin {
+                        denom: coin.denom.clone(),
+                        amount: Uint128::from(prize_pool / num_leaders + remainder),
+                    }
+                })
+                .filter(|coin| !coin.amount.is_zero())
+                .collect();
+            (leader, prize)
+        })
+        .filter(|(_, prize)| !prize.is_empty())
+        .map(|(leader, prize)| {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: leader.to_string(),
+                amount: prize,
+            })
+        })
+        .collect())
+}
+
+/// Helper function for getting a game result based on host and opp moves
+fn get_result(host_move: GameMove, opp_move: GameMove) -> GameResult {
+    match (host_move, opp_move) {
+        // rock and paper
+        (GameMove::Rock, GameMove::Paper) => GameResult::Player2Wins,
+        (GameMove::Paper, GameMove::Rock) => GameResult::Player1Wins,
+        // paper and scissors
+        (GameMove::Paper, GameMove::Scissors) => GameResult::Player2Wins,
+        (GameMove::Scissors, GameMove::Paper) => GameResult::Player1Wins,
+        // scissors and rock
+        (GameMove::Scissors, GameMove::Rock) => GameResult::Player2Wins,
+        (GameMove::Rock, GameMove::Scissors) => GameResult::Player1Wins,
+        // remaining are ties
+        (GameMove::Rock, GameMove::Rock) => GameResult::Tie,
+        (GameMove::Scissors, GameMove::Scissors) => GameResult::Tie,
+        (GameMove::Paper, GameMove::Paper) => GameResult::Tie,
+    }
+}
+
+/// Helper function for paying out a finished game and recording it on the leaderboard
+fn settle_game(
+    mut deps: DepsMut,
+    env: Env,
+    game_state: &GameState,
+    game_result: GameResult,
+) -> Result<Response, ContractError> {
+    // Refunded games are played as if they never happened
+    let refunded = game_result == GameResult::Tie
+        && matches!(
+            &game_state.tie_break,
+            Some(TieBreak {
+                policy: TiePolicy::Refund,
+                ..
+            })
+        );
+
+    let leaderboard_res = if refunded {
+        Response::new()
+    } else {
+        update_leaderboard(deps.branch(), env.clone(), game_state, game_result.clone())?
+    };
+
+    let event = events::game_ended(game_state, &game_result);
+    let hooks = game_event_hooks(
+        deps.storage,
+        GameEvent::GameEnded {
+            player1: game_state.player1.clone(),
+            player2: game_state.player2.clone(),
+            result: game_result.clone(),
+        },
+    )?;
+
+    let side_bet_payouts = settle_side_bets(
+        deps.storage,
+        game_state.id,
+        match game_result {
+            GameResult::Player1Wins => Some(BetSide::Player1),
+            GameResult::Player2Wins => Some(BetSide::Player2),
+            GameResult::Tie => None,
+        },
+    )?;
+
+    let winner_and_loser = match game_result {
+        GameResult::Player1Wins => Some((game_state.player1.clone(), game_state.player2.clone())),
+        GameResult::Player2Wins => Some((game_state.player2.clone(), game_state.player1.clone())),
+        GameResult::Tie => None,
+    };
+
+    let res = match (game_state.tournament_id, game_state.league_id) {
+        // Bracket games don't pay out, the winner moves on instead
+        (Some(tournament_id), _) => {
+            let (winner, loser) = winner_and_loser.ok_or(ContractError::TournamentTie {})?;
+            advance_tournament(deps, env, tournament_id, winner, loser)?
+        }
+        // Neither do league fixtures, they score league points
+        (_, Some(league_id)) => {
+            advance_league(deps.storage, &env, league_id, game_state, game_result)?
+        }
+        // Rematches are only offered for coin bets, the NFTs just changed hands
+        (None, None) if matches!(game_state.player1_stake, Asset::Nft(_)) => match winner_and_loser
+        {
+            Some((winner, _)) => send_winnings(deps.storage, winner, game_state)?,
+            None => send_refunds(game_state)?,
+        },
+        (None, None) => {
+            let key = (game_state.player1.as_bytes(), game_state.player2.as_bytes());
+            let maybe_previous_offer = REMATCH_OFFERS.may_load(deps.storage, key)?;
+
+            // Leave an offer behind so the players can play again without queueing
+            save_rematch_offer(
+                deps.storage,
+                key,
+                &RematchOffer {
+                    player1: game_state.player1.clone(),
+                    player2: game_state.player2.clone(),
+                    bet_amount: stake_coins(&game_state.player1_stake),
+                    num_hands_to_win: game_state.num_hands_to_win,
+                    tie_break: game_state.tie_break.clone(),
+                    mode: game_state.mode.clone(),
+                    expires_at: env.block.time.nanos() + REMATCH_WINDOW,
+                    offered_by: None,
+                },
+            )?;
+
+            let mut res = match winner_and_loser {
+                Some((winner, _)) => send_winnings(deps.storage, winner, game_state)?,
+                None => send_refunds(game_state)?,
+            };
+
+            // Refund a bet left on an older offer between the same players
+            if let Some(RematchOffer {
+                offered_by: Some(offered_by),
+                bet_amount,
+                ..
+            }) = maybe_previous_offer
+            {
+                res = res.add_message(BankMsg::Send {
+                    to_address: offered_by.into(),
+                    amount: bet_amount,
+                });
+            }
+
+            res
+        }
+    };
+
+    Ok(merge_responses(res, leaderboard_res)
+        .add_messages(side_bet_payouts)
+        .add_event(event)
+        .add_submessages(hooks))
+}
+
+/// Helper function for paying out a game's side bets pari-mutuel, or refunding them all when
+/// there's no winner. Bets in a denom nobody backed the winner with are refunded as well,
+/// and rounding dust stays in the contract
+fn settle_side_bets(
+    storage: &mut dyn Storage,
+    game_id: u64,
+    winning_side: Option<BetSide>,
+) -> StdResult<Vec<BankMsg>> {
+    let pool = match SIDE_BET_POOLS.may_load(storage, game_id.into())? {
+        Some(pool) => pool,
+        None => return Ok(vec![]),
+    };
+    remove_side_bet_pool(storage, game_id.into())?;
+
+    let side_bets = SIDE_BETS
+        .prefix(game_id.into())
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, side_bet)| side_bet))
+        .collect::<StdResult<Vec<SideBet>>>()?;
+
+    let pool_amount = |coins: &[Coin], denom: &str| {
+        coins
+            .iter()
+            .find(|coin| coin.denom == denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default()
+    };
+
+    let mut payouts = vec![];
+    let mut paid_out = vec![];
+    for side_bet in side_bets {
+        SIDE_BETS.remove(storage, (game_id.into(), &side_bet.bettor));
+
+        let denom = &side_bet.amount.denom;
+        let payout = match &winning_side {
+            Some(winning_side) => {
+                let (winning_pool, losing_pool) = match winning_side {
+                    BetSide::Player1 => (&pool.player1, &pool.player2),
+                    BetSide::Player2 => (&pool.player2, &pool.player1),
+                };
+                let backed = pool_amount(winning_pool, denom);
+                if backed.is_zero() {
+                    side_bet.amount.amount
+                } else if side_bet.on == *winning_side {
+                    side_bet.amount.amount
+                        + side_bet
+                            .amount
+                            .amount
+                            .multiply_ratio(pool_amount(losing_pool, denom), backed)
+                } else {
+                    Uint128::zero()
+                }
+            }
+            None => side_bet.amount.amount,
+        };
+
+        if !payout.is_zero() {
+            let amount = vec![Coin {
+                denom: denom.clone(),
+                amount: payout,
+            }];
+            add_coins(&mut paid_out, &amount);
+            payouts.push(BankMsg::Send {
+                to_address: side_bet.bettor.to_string(),
+                amount,
+            });
+        }
+    }
+
+    // Winners' shares are rounded down, what's left of the pool is kept as fees
+    let mut pooled = pool.player1.clone();
+    add_coins(&mut pooled, &pool.player2);
+    let mut dust = sub_coins(&pooled, &paid_out).unwrap_or_default();
+    dust.retain(|coin| !coin.amount.is_zero());
+    for fee in &dust {
+        LIFETIME_FEES.update(storage, &fee.denom, |total| -> StdResult<_> {
+            Ok(total.unwrap_or_default() + fee.amount)
+        })?;
+    }
+    accrue_fees(storage, &dust)?;
+
+    Ok(payouts)
+}
+
+/// Helper function for notifying every registered hook about a game event
+fn game_event_hooks(storage: &dyn Storage, event: GameEvent) -> StdResult<Vec<SubMsg>> {
+    let msg = GameEventHookMsg { event };
+    HOOKS.prepare_hooks(storage, |hook| {
+        Ok(SubMsg::reply_on_error(
+            msg.clone().into_cosmos_msg(hook)?,
+            HOOK_REPLY_ID,
+        ))
+    })
+}
+
+/// Helper function for notifying every registered hook about a new game
+fn game_created_hooks(storage: &dyn Storage, game_state: &GameState) -> StdResult<Vec<SubMsg>> {
+    game_event_hooks(
+        storage,
+        GameEvent::GameCreated {
+            player1: game_state.player1.clone(),
+            player2: game_state.player2.clone(),
+            bet_amount: stake_coins(&game_state.player1_stake),
+            num_hands_to_win: game_state.num_hands_to_win,
+        },
+    )
+}
+
+/// Helper function for handing out game ids, shared by regular and house games
+fn next_game_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = LATEST_GAME_ID.may_load(storage)?.unwrap_or_default() + 1;
+    LATEST_GAME_ID.save(storage, &id)?;
+    Ok(id)
+}
+
+/// Helper function for storing a freshly created game under the next game id
+fn save_new_game(storage: &mut dyn Storage, game_state: &mut GameState) -> StdResult<Response> {
+    game_state.id = next_game_id(storage)?;
+
+    save_game_state(
+        storage,
+        (game_state.player1.as_bytes(), game_state.player2.as_bytes()),
+        game_state,
+    )?;
+
+    Ok(Response::new()
+        .add_event(events::game_created(game_state))
+        .add_submessages(game_created_hooks(storage, game_state)?))
+}
+
+/// Helper function for moving a running total from what a record held to what it holds now
+fn update_total(
+    storage: &mut dyn Storage,
+    total: Item<Vec<Coin>>,
+    before: &[Coin],
+    after: &[Coin],
+) -> StdResult<()> {
+    let mut coins = total.may_load(storage)?.unwrap_or_default();
+    add_coins(&mut coins, after);
+    for coin in before {
+        if let Some(held) = coins.iter_mut().find(|held| held.denom == coin.denom) {
+            held.amount = held.amount.saturating_sub(coin.amount);
+        }
+    }
+    coins.retain(|coin| !coin.amount.is_zero());
+    total.save(storage, &coins)
+}
+
+/// Helper function for saving a record while keeping the running total of what it holds in step
+fn save_tracked<'a, K, T>(
+    storage: &mut dyn Storage,
+    map: Map<'a, K, T>,
+    key: K,
+    value: &T,
+    total: Item<Vec<Coin>>,
+    held: fn(&T) -> Vec<Coin>,
+) -> StdResult<()>
+where
+    K: PrimaryKey<'a> + Clone,
+    T: Serialize + DeserializeOwned,
+{
+    let before = map.may_load(storage, key.clone())?;
+    update_total(
+        storage,
+        total,
+        &before.as_ref().map(held).unwrap_or_default(),
+        &held(value),
+    )?;
+    map.save(storage, key, value)
+}
+
+/// Helper function for removing a record while keeping the running total of what it held in step
+fn remove_tracked<'a, K, T>(
+    storage: &mut dyn Storage,
+    map: Map<'a, K, T>,
+    key: K,
+    total: Item<Vec<Coin>>,
+    held: fn(&T) -> Vec<Coin>,
+) -> StdResult<()>
+where
+    K: PrimaryKey<'a> + Clone,
+    T: Serialize + DeserializeOwned,
+{
+    if let Some(before) = map.may_load(storage, key.clone())? {
+        update_total(storage, total, &held(&before), &[])?;
+    }
+    map.remove(storage, key);
+    Ok(())
+}
+
+/// Helper function for the coins a game holds, its stakes less relayer fees already paid
+fn game_escrow(game_state: &GameState) -> Vec<Coin> {
+    let (player1_stake, player2_stake) = game_stakes(game_state);
+    let mut coins = stake_coins(&player1_stake);
+    add_coins(&mut coins, &stake_coins(&player2_stake));
+    coins
+}
+
+/// Helper function for saving a game, its stakes count towards the game escrow
+fn save_game_state(
+    storage: &mut dyn Storage,
+    key: (&[u8], &[u8]),
+    game_state: &GameState,
+) -> StdResult<()> {
+    let before = game_states().may_load(storage, key)?;
+    update_total(
+        storage,
+        GAME_ESCROW,
+        &before.as_ref().map(game_escrow).unwrap_or_default(),
+        &game_escrow(game_state),
+    )?;
+    game_states().save(storage, key, game_state)
+}
+
+/// Helper function for removing a game, taking its stakes out of the game escrow
+fn remove_game_state(storage: &mut dyn Storage, key: (&[u8], &[u8])) -> StdResult<()> {
+    if let Some(before) = game_states().may_load(storage, key)? {
+        update_total(storage, GAME_ESCROW, &game_escrow(&before), &[])?;
+    }
+    game_states().remove(storage, key)
+}
+
+/// Helper function for the player bet a house game holds, the house stake is exposure
+fn house_game_bet(house_game: &HouseGame) -> Vec<Coin> {
+    house_game.bet_amount.clone()
+}
+
+/// Helper function for saving a house game, the player bet counts towards the game escrow
+fn save_house_game(
+    storage: &mut dyn Storage,
+    player: &Addr,
+    house_game: &HouseGame,
+) -> StdResult<()> {
+    save_tracked(
+        storage,
+        HOUSE_GAMES,
+        player,
+        house_game,
+        GAME_ESCROW,
+        house_game_bet,
+    )
+}
+
+/// Helper function for removing a house game, taking the player bet out of the game escrow
+fn remove_house_game(storage: &mut dyn Storage, player: &Addr) -> StdResult<()> {
+    remove_tracked(storage, HOUSE_GAMES, player, GAME_ESCROW, house_game_bet)
+}
+
+/// Helper function for the coins a queued player has put up
+fn unmatched_stake(unmatched_player: &UnmatchedPlayer) -> Vec<Coin> {
+    stake_coins(&unmatched_player.stake)
+}
+
+/// Helper function for queueing a player, their stake counts towards the queue deposits
+fn save_unmatched_player(
+    storage: &mut dyn Storage,
+    key: (String, U8Key),
+    unmatched_player: &UnmatchedPlayer,
+) -> StdResult<()> {
+    save_tracked(
+        storage,
+        UNMATCHED_PLAYERS,
+        key,
+        unmatched_player,
+        QUEUE_DEPOSITS,
+        unmatched_stake,
+    )
+}
+
+/// Helper function for taking a player out of the queue and their stake out of the queue deposits
+fn remove_unmatched_player(storage: &mut dyn Storage, key: (String, U8Key)) -> StdResult<()> {
+    remove_tracked(
+        storage,
+        UNMATCHED_PLAYERS,
+        key,
+        QUEUE_DEPOSITS,
+        unmatched_stake,
+    )
+}
+
+/// Helper function for the coins a rematch offer holds, only the offering player has paid in
+fn rematch_deposit(rematch_offer: &RematchOffer) -> Vec<Coin> {
+    match rematch_offer.offered_by {
+        Some(_) => rematch_offer.bet_amount.clone(),
+        None => vec![],
+    }
+}
+
+/// Helper function for saving a rematch offer, the offered bet counts towards the queue deposits
+fn save_rematch_offer(
+    storage: &mut dyn Storage,
+    key: (&[u8], &[u8]),
+    rematch_offer: &RematchOffer,
+) -> StdResult<()> {
+    save_tracked(
+        storage,
+        REMATCH_OFFERS,
+        key,
+        rematch_offer,
+        QUEUE_DEPOSITS,
+        rematch_deposit,
+    )
+}
+
+/// Helper function for removing a rematch offer, taking the offered bet out of the queue deposits
+fn remove_rematch_offer(storage: &mut dyn Storage, key: (&[u8], &[u8])) -> StdResult<()> {
+    remove_tracked(
+        storage,
+        REMATCH_OFFERS,
+        key,
+        QUEUE_DEPOSITS,
+        rematch_deposit,
+    )
+}
+
+/// Helper function for the buy-ins a tournament holds until it's paid out
+fn tournament_escrow(tournament: &Tournament) -> Vec<Coin> {
+    let mut coins = vec![];
+    if let TournamentStatus::Registration | TournamentStatus::InProgress = tournament.status {
+        for _ in &tournament.players {
+            add_coins(&mut coins, &tournament.buy_in);
+        }
+    }
+    coins
+}
+
+/// Helper function for saving a tournament, keeping the competition escrow in step with its buy-ins
+fn save_tournament(
+    storage: &mut dyn Storage,
+    key: U32Key,
+    tournament: &Tournament,
+) -> StdResult<()> {
+    save_tracked(
+        storage,
+        TOURNAMENTS,
+        key,
+        tournament,
+        COMPETITION_ESCROW,
+        tournament_escrow,
+    )
+}
+
+/// Helper function for the entry fees a league holds until it's paid out
+fn league_escrow(league: &League) -> Vec<Coin> {
+    let mut coins = vec![];
+    if let LeagueStatus::Registration | LeagueStatus::InProgress = league.status {
+        for _ in &league.paid {
+            add_coins(&mut coins, &league.entry_fee);
+        }
+    }
+    coins
+}
+
+/// Helper function for saving a league, keeping the competition escrow in step with its entry fees
+fn save_league(storage: &mut dyn Storage, key: U32Key, league: &League) -> StdResult<()> {
+    save_tracked(
+        storage,
+        LEAGUES,
+        key,
+        league,
+        COMPETITION_ESCROW,
+        league_escrow,
+    )
+}
+
+/// Helper function for the coins bet on both sides of a game
+fn side_bet_pool_coins(pool: &SideBetPool) -> Vec<Coin> {
+    let mut coins = pool.player1.clone();
+    add_coins(&mut coins, &pool.player2);
+    coins
+}
+
+/// Helper function for saving a side bet pool, keeping the side bet total in step
+fn save_side_bet_pool(storage: &mut dyn Storage, key: U64Key, pool: &SideBetPool) -> StdResult<()> {
+    save_tracked(
+        storage,
+        SIDE_BET_POOLS,
+        key,
+        pool,
+        SIDE_BET_TOTAL,
+        side_bet_pool_coins,
+    )
+}
+
+/// Helper function for removing a settled side bet pool from the side bet total
+fn remove_side_bet_pool(storage: &mut dyn Storage, key: U64Key) -> StdResult<()> {
+    remove_tracked(
+        storage,
+        SIDE_BET_POOLS,
+        key,
+        SIDE_BET_TOTAL,
+        side_bet_pool_coins,
+    )
+}
+
+/// Helper function for saving a penalty bond, keeping the bond total in step
+fn save_penalty_bond(storage: &mut dyn Storage, player: &Addr, bond: &Vec<Coin>) -> StdResult<()> {
+    save_tracked(
+        storage,
+        PENALTY_BONDS,
+        player,
+        bond,
+        PENALTY_BOND_TOTAL,
+        Vec::clone,
+    )
+}
+
+/// Helper function for removing a penalty bond from the bond total
+fn remove_penalty_bond(storage: &mut dyn Storage, player: &Addr) -> StdResult<()> {
+    remove_tracked(
+        storage,
+        PENALTY_BONDS,
+        player,
+        PENALTY_BOND_TOTAL,
+        Vec::clone,
+    )
+}
+
+/// Helper function for the rewards a referrer hasn't claimed yet
+fn referral_balance(stats: &ReferralStats) -> Vec<Coin> {
+    stats.balance.clone()
+}
+
+/// Helper function for saving referral stats, keeping the referral total in step with the balance
+fn save_referral_stats(
+    storage: &mut dyn Storage,
+    referrer: &Addr,
+    stats: &ReferralStats,
+) -> StdResult<()> {
+    save_tracked(
+        storage,
+        REFERRAL_STATS,
+        referrer,
+        stats,
+        REFERRAL_TOTAL,
+        referral_balance,
+    )
+}
+
+/// Helper function for the part of a distribution's prize pool not paid out so far
+fn prizes_remaining(distribution: &PrizeDistribution) -> Vec<Coin> {
+    distribution
+        .prize_pool
+        .iter()
+        .map(|coin| {
+            let amount_paid = distribution.payout_curve[..distribution.num_paid as usize]
+                .iter()
+                .map(|percentage| coin.amount.multiply_ratio(*percentage as u128, 100u128))
+                .fold(Uint128::zero(), |total, amount| total + amount);
+            Coin {
+                denom: coin.denom.clone(),
+                amount: coin.amount - amount_paid,
+            }
+        })
+        .filter(|coin| !coin.amount.is_zero())
+        .collect()
+}
+
+/// Helper function for the prizes a distribution still owes, a complete one handed the rest back
+/// to the prize pool
+fn unpaid_prizes(distribution: &PrizeDistribution) -> Vec<Coin> {
+    if distribution.complete {
+        vec![]
+    } else {
+        prizes_remaining(distribution)
+    }
+}
+
+/// Helper function for saving a prize distribution, keeping the unpaid prizes in step
+fn save_prize_distribution(
+    storage: &mut dyn Storage,
+    season_id: u32,
+    distribution: &PrizeDistribution,
+) -> StdResult<()> {
+    save_tracked(
+        storage,
+        PRIZE_DISTRIBUTIONS,
+        U32Key::new(season_id),
+        distribution,
+        PRIZE_DISTRIBUTION_TOTAL,
+        unpaid_prizes,
+    )
+}
+
+/// Helper function for adding up what every record in a range holds
+fn sum_held<T>(
+    records: impl Iterator<Item = StdResult<(Vec<u8>, T)>>,
+    held: fn(&T) -> Vec<Coin>,
+) -> StdResult<Vec<Coin>> {
+    let mut coins = vec![];
+    for item in records {
+        let (_, value) = item?;
+        add_coins(&mut coins, &held(&value));
+    }
+    Ok(coins)
+}
+
+/// Helper function for rebuilding the running treasury totals from the records, it walks
+/// all of them so it only runs on migration
+fn seed_treasury_totals(storage: &mut dyn Storage) -> StdResult<()> {
+    let mut game_escrow_total = sum_held(
+        game_states().range(storage, None, None, Order::Ascending),
+        game_escrow,
+    )?;
+    add_coins(
+        &mut game_escrow_total,
+        &sum_held(
+            HOUSE_GAMES.range(storage, None, None, Order::Ascending),
+            house_game_bet,
+        )?,
+    );
+
+    let mut queue_deposits = sum_held(
+        UNMATCHED_PLAYERS.range(storage, None, None, Order::Ascending),
+        unmatched_stake,
+    )?;
+    add_coins(
+        &mut queue_deposits,
+        &sum_held(
+            REMATCH_OFFERS.range(storage, None, None, Order::Ascending),
+            rematch_deposit,
+        )?,
+    );
+
+    let mut competition_escrow = sum_held(
+        TOURNAMENTS.range(storage, None, None, Order::Ascending),
+        tournament_escrow,
+    )?;
+    add_coins(
+        &mut competition_escrow,
+        &sum_held(
+            LEAGUES.range(storage, None, None, Order::Ascending),
+            league_escrow,
+        )?,
+    );
+
+    let side_bets = sum_held(
+        SIDE_BET_POOLS.range(storage, None, None, Order::Ascending),
+        side_bet_pool_coins,
+    )?;
+    let penalty_bonds = sum_held(
+        PENALTY_BONDS.range(storage, None, None, Order::Ascending),
+        Vec::clone,
+    )?;
+    let referral_balances = sum_held(
+        REFERRAL_STATS.range(storage, None, None, Order::Ascending),
+        referral_balance,
+    )?;
+    let prize_distributions = sum_held(
+        PRIZE_DISTRIBUTIONS.range(storage, None, None, Order::Ascending),
+        unpaid_prizes,
+    )?;
+
+    for (total, mut coins) in [
+        (GAME_ESCROW, game_escrow_total),
+        (QUEUE_DEPOSITS, queue_deposits),
+        (COMPETITION_ESCROW, competition_escrow),
+        (SIDE_BET_TOTAL, side_bets),
+        (PENALTY_BOND_TOTAL, penalty_bonds),
+        (REFERRAL_TOTAL, referral_balances),
+        (PRIZE_DISTRIBUTION_TOTAL, prize_distributions),
+    ] {
+        coins.retain(|coin| !coin.amount.is_zero());
+        total.save(storage, &coins)?;
+    }
+    Ok(())
+}
+
+/// Helper function for the game_state attribute, serializing can fail instead of aborting the contract
+fn game_state_json(game_state: &GameState) -> StdResult<String> {
+    Ok(String::from_utf8(to_vec(game_state)?)?)
+}
+
+/// Helper function for appending the messages, attributes and events of one response to another
+fn merge_responses(res: Response, other: Response) -> Response {
+    res.add_submessages(other.messages)
+        .add_attributes(other.attributes)
+        .add_events(other.events)
+}
+
+/// Helper function for what each player put up for a game
+fn game_stakes(game_state: &GameState) -> (Asset, Asset) {
+    // Relayer fees were checked against the stake when they were paid
+    let less_relayer_fees = |stake: &Asset, relayer_fees: &[Coin]| match stake {
+        Asset::Coins(coins) => Asset::Coins(sub_coins(coins, relayer_fees).unwrap_or_default()),
+        Asset::Nft(nft) => Asset::Nft(nft.clone()),
+    };
+
+    (
+        less_relayer_fees(&game_state.player1_stake, &game_state.player1_relayer_fees),
+        less_relayer_fees(&game_state.player2_stake, &game_state.player2_relayer_fees),
+    )
+}
+
+/// Helper function for the coins in a stake, NFT stakes have none
+fn stake_coins(stake: &Asset) -> Vec<Coin> {
+    match stake {
+        Asset::Coins(coins) => coins.clone(),
+        Asset::Nft(_) => vec![],
+    }
+}
+
+/// Helper function for sending both players' bets to the winner, less the fee
+fn send_winnings(
+    storage: &mut dyn Storage,
+    to_address: Addr,
+    game_state: &GameState,
+) -> StdResult<Response> {
+    let (player1_stake, player2_stake) = game_stakes(game_state);
+    let player1_stake = take_fee(storage, player1_stake, &game_state.player1)?;
+    let player2_stake = take_fee(storage, player2_stake, &game_state.player2)?;
+
+    Ok(Response::new()
+        .add_messages(send_asset(&to_address, player1_stake)?)
+        .add_messages(send_asset(&to_address, player2_stake)?))
+}
+
+/// Helper function for keeping the configured fee out of a coin stake, NFTs are paid out whole.
+/// The staker's referrer is credited their share of the fee straight away
+fn take_fee(storage: &mut dyn Storage, stake: Asset, staker: &Addr) -> StdResult<Asset> {
+    let (coins, config) = match (stake, FEE_CONFIG.may_load(storage)?) {
+        (Asset::Coins(coins), Some(config)) if config.fee_percentage > 0 => (coins, config),
+        (stake, _) => return Ok(stake),
+    };
+
+    let fees = coins_percentage(&coins, config.fee_percentage);
+    let mut contract_fees = fees.clone();
+    if let Some(referrer) = REFERRALS.may_load(storage, staker)? {
+        let reward = coins_percentage(&fees, config.referral_percentage);
+        if !reward.is_empty() {
+            let mut stats = REFERRAL_STATS
+                .may_load(storage, &referrer)?
+                .unwrap_or_default();
+            add_coins(&mut stats.earned, &reward);
+            add_coins(&mut stats.balance, &reward);
+            save_referral_stats(storage, &referrer, &stats)?;
+            contract_fees = sub_coins(&fees, &reward).unwrap_or_default();
+            contract_fees.retain(|coin| !coin.amount.is_zero());
+        }
+    }
+
+    let prize_share = coins_percentage(&contract_fees, config.prize_pool_percentage);
+    if !prize_share.is_empty() {
+        let mut prize_pool = PRIZE_POOL.may_load(storage)?.unwrap_or_default();
+        add_coins(&mut prize_pool, &prize_share);
+        PRIZE_POOL.save(storage, &prize_pool)?;
+        contract_fees = sub_coins(&contract_fees, &prize_share).unwrap_or_default();
+        contract_fees.retain(|coin| !coin.amount.is_zero());
+    }
+
+    for fee in &fees {
+        LIFETIME_FEES.update(storage, &fee.denom, |total| -> StdResult<_> {
+            Ok(total.unwrap_or_default() + fee.amount)
+        })?;
+    }
+    accrue_fees(storage, &contract_fees)?;
+
+    let mut winnings = sub_coins(&coins, &fees).unwrap_or_default();
+    winnings.retain(|coin| !coin.amount.is_zero());
+    Ok(Asset::Coins(winnings))
+}
+
+/// Helper function for keeping coins in the contract until the admin withdraws them
+fn accrue_fees(storage: &mut dyn Storage, fees: &[Coin]) -> StdResult<()> {
+    for fee in fees {
+        ACCRUED_FEES.update(storage, &fee.denom, |total| -> StdResult<_> {
+            Ok(total.unwrap_or_default() + fee.amount)
+        })?;
+    }
+    Ok(())
+}
+
+/// Helper function for sending both players their bet back
+fn send_refunds(game_state: &GameState) -> StdResult<Response> {
+    let (player1_stake, player2_stake) = game_stakes(game_state);
+
+    Ok(Response::new()
+        .add_messages(send_asset(&game_state.player1, player1_stake)?)
+        .add_messages(send_asset(&game_state.player2, player2_stake)?))
+}
+
+/// Helper function for sending coins with the bank module or an NFT through its collection.
+/// Zero coins are left out, and nothing is sent when no coins are left
+fn send_asset(to_address: &Addr, asset: Asset) -> StdResult<Option<CosmosMsg>> {
+    Ok(match asset {
+        Asset::Coins(mut amount) => {
+            amount.retain(|coin| !coin.amount.is_zero());
+            if amount.is_empty() {
+                return Ok(None);
+            }
+            Some(CosmosMsg::Bank(BankMsg::Send {
+                to_address: to_address.to_string(),
+                amount,
+            }))
+        }
+        Asset::Nft(nft) => Some(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: nft.collection.into(),
+            msg: to_binary(&Cw721ExecuteMsg::TransferNft {
+                recipient: to_address.to_string(),
+                token_id: nft.token_id,
+            })?,
+            funds: vec![],
+        })),
+    })
+}
+
+/// Helper function for minting a trophy to a winner, if a trophy contract is configured
+fn mint_trophy(
+    storage: &dyn Storage,
+    env: &Env,
+    token_id: String,
+    owner: &Addr,
+    name: String,
+    description: String,
+    mut attributes: Vec<Trait>,
+) -> StdResult<Vec<SubMsg>> {
+    let trophy_contract = match TROPHY_CONTRACT.may_load(storage)? {
+        Some(trophy_contract) => trophy_contract,
+        None => return Ok(vec![]),
+    };
+
+    attributes.push(trait_attribute(
+        "date",
+        env.block.time.seconds().to_string(),
+    ));
+
+    let mint_msg = Cw721ExecuteMsg::Mint(MintMsg {
+        token_id,
+        owner: owner.to_string(),
+        token_uri: None,
+        extension: TrophyMetadata {
+            name,
+            description,
+            attributes,
+        },
+    });
+
+    // Only reply on error so a failed mint doesn't roll back the payouts
+    Ok(vec![SubMsg::reply_on_error(
+        WasmMsg::Execute {
+            contract_addr: trophy_contract.into(),
+            msg: to_binary(&mint_msg)?,
+            funds: vec![],
+        },
+        TROPHY_MINT_REPLY_ID,
+    )])
+}
+
+/// Helper function for building a trophy metadata attribute
+fn trait_attribute(trait_type: &str, value: String) -> Trait {
+    Trait {
+        trait_type: trait_type.to_string(),
+        value,
+    }
+}
+
+/// Helper function for adding coins to a balance, merging coins of the same denom
+fn add_coins(balance: &mut Vec<Coin>, coins: &[Coin]) {
+    for coin in coins {
+        match balance.iter_mut().find(|b| b.denom == coin.denom) {
+            Some(existing) => existing.amount += coin.amount,
+            None => balance.push(coin.clone()),
+        }
+    }
+}
+
+/// Helper function for taking coins out of a balance, None if the balance doesn't cover them
+fn sub_coins(balance: &[Coin], coins: &[Coin]) -> Option<Vec<Coin>> {
+    let mut balance = balance.to_vec();
+    for coin in coins {
+        let existing = balance.iter_mut().find(|b| b.denom == coin.denom)?;
+        existing.amount = existing.amount.checked_sub(coin.amount).ok()?;
+    }
+    Some(balance)
+}
+
+/// Helper function for taking a percentage of every coin, dropping empty coins
+fn coins_percentage(coins: &[Coin], percentage: u8) -> Vec<Coin> {
+    coins
+        .iter()
+        .map(|coin| Coin {
+            denom: coin.denom.clone(),
+            amount: coin.amount.multiply_ratio(percentage as u128, 100u128),
+        })
+        .filter(|coin| !coin.amount.is_zero())
+        .collect()
+}
+
+/// Helper function for multiplying every coin, failing instead of overflowing
+fn multiply_coins(coins: &[Coin], factor: u128) -> StdResult<Vec<Coin>> {
+    coins
+        .iter()
+        .map(|coin| {
+            Ok(Coin {
+                denom: coin.denom.clone(),
+                amount: coin.amount.checked_mul(Uint128::from(factor))?,
+            })
+        })
+        .collect()
+}
+
+// Function for making appriopriate payments and emitting apprioriate message attributes
+// based on the result of a hand
+fn handle_hand_result(
+    storage: &mut dyn Storage,
+    env: &Env,
+    updated_game_state: &mut GameState,
+    player1_game_move: GameMove,
+    player2_game_move: GameMove,
+    player1_revealed_first: bool,
+) -> Result<Response, ContractError> {
+    let mut result = get_result(player1_game_move.clone(), player2_game_move.clone());
+
+    // Keep every hand for move based achievements
+    updated_game_state
+        .player1_moves
+        .push(player1_game_move.clone());
+    updated_game_state
+        .player2_moves
+        .push(player2_game_move.clone());
+
+    // Past the tie cap every hand is sudden death
+    let sudden_death = matches!(
+        &updated_game_state.tie_break,
+        Some(TieBreak { max_ties, policy: TiePolicy::SuddenDeath })
+            if updated_game_state.hands_tied >= *max_ties
+    );
+    if sudden_death && result == GameResult::Tie {
+        // so a tied hand goes to whoever revealed first
+        result = if player1_revealed_first {
+            GameResult::Player1Wins
+        } else {
+            GameResult::Player2Wins
+        };
+    }
+
+    // Hand based achievements unlock as soon as the hand is played, game based ones are
+    // checked against the stored profile in case a definition was added since the last game
+    let hand_winner = match result {
+        GameResult::Player1Wins => Some((updated_game_state.player1.clone(), true)),
+        GameResult::Player2Wins => Some((updated_game_state.player2.clone(), false)),
+        GameResult::Tie => None,
+    };
+    let achievement_events = match hand_winner {
+        Some((address, is_player1)) => {
+            let profile = leaderboard()
+                .may_load(storage, address.as_bytes())?
+                .unwrap_or_else(|| new_user_profile(address.clone()));
+            let game_moves = if is_player1 {
+                &updated_game_state.player1_moves
+            } else {
+                &updated_game_state.player2_moves
+            };
+            unlock_achievements(
+                storage,
+                env,
+                &profile,
+                false,
+                game_moves,
+                None,
+                hand_streak(updated_game_state, is_player1),
+            )?
+        }
+        None => vec![],
+    };
+
+    let res = match result {
+        GameResult::Player1Wins => {
+            // increment player 1 win counter
+            updated_game_state.player1_hands_won += 1;
+            // check if game is over
+            if updated_game_state.player1_hands_won == updated_game_state.num_hands_to_win
+                || sudden_death
+            {
+                updated_game_state.result = Some(result);
+
+                // the winner is paid once the game is settled
+                Ok(Response::new()
+                    .add_attribute("action", "reveal_move")
+                    .add_attribute(
+                        "players",
+                        format!(
+                            "{},{}",
+                            updated_game_state.clone().player1,
+                            updated_game_state.clone().player2
+                        ),
+                    )
+                    .add_attribute("game_won", updated_game_state.clone().player1)
+                    .add_attribute("player1_game_move", player1_game_move.to_string())
+                    .add_attribute("player2_game_move", player2_game_move.to_string())
+                    .add_attribute("game_state", game_state_json(updated_game_state)?))
+            } else {
+                Ok(Response::new()
+                    .add_attribute("action", "reveal_move")
+                    .add_attribute(
+                        "players",
+                        format!(
+                            "{},{}",
+                            updated_game_state.clone().player1,
+                            updated_game_state.clone().player2
+                        ),
+                    )
+                    .add_attribute("hand_won", updated_game_state.clone().player1)
+                    .add_attribute("player1_game_move", player1_game_move.to_string())
+                    .add_attribute("player2_game_move", player2_game_move.to_string())
+                    .add_attribute("game_state", game_state_json(updated_game_state)?))
+            }
+        }
+        GameResult::Player2Wins => {
+            // increment player 2 win counter
+            updated_game_state.player2_hands_won += 1;
+            // check if game is over
+            if updated_game_state.player2_hands_won == updated_game_state.num_hands_to_win
+                || sudden_death
+            {
+                updated_game_state.result = Some(result);
+
+                // the winner is paid once the game is settled
+                Ok(Response::new()
+                    .add_attribute("action", "reveal_move")
+                    .add_attribute(
+                        "players",
+                        format!(
+                            "{},{}",
+                            updated_game_state.clone().player1,
+                            updated_game_state.clone().player2
+                        ),
+                    )
+                    .add_attribute("game_won", updated_game_state.clone().player2)
+                    .add_attribute("player1_game_move", player1_game_move.to_string())
+                    .add_attribute("player2_game_move", player2_game_move.to_string())
+                    .add_attribute("game_state", game_state_json(updated_game_state)?))
+            } else {
+                Ok(Response::new()
+                    .add_attribute("action", "reveal_move")
+                    .add_attribute(
+                        "players",
+                        format!(
+                            "{},{}",
+                            updated_game_state.clone().player1,
+                            updated_game_state.clone().player2
+                        ),
+                    )
+                    .add_attribute("hand_won", updated_game_state.clone().player2)
+                    .add_attribute("player1_game_move", player1_game_move.to_string())
+                    .add_attribute("player2_game_move", player2_game_move.to_string())
+                    .add_attribute("game_state", game_state_json(updated_game_state)?))
+            }
+        }
+        GameResult::Tie => {
+            // increment tie counter
+            updated_game_state.hands_tied += 1;
+
+            // check if the tie cap ends the game in a draw
+            let hand_won = match &updated_game_state.tie_break {
+                Some(TieBreak { max_ties, policy })
+                    if updated_game_state.hands_tied >= *max_ties
+                        && *policy != TiePolicy::SuddenDeath =>
+                {
+                    updated_game_state.result = Some(GameResult::Tie);
+                    "game_tied"
+                }
+                _ => "hand_won",
+            };
+
+            Ok(Response::new()
+                .add_attribute("action", "reveal_move")
+                .add_attribute(
+                    "players",
+                    format!(
+                        "{},{}",
+                        updated_game_state.clone().player1,
+                        updated_game_state.clone().player2
+                    ),
+                )
+                .add_attribute(hand_won, "tie")
+                .add_attribute("player1_game_move", player1_game_move.to_string())
+                .add_attribute("player2_game_move", player2_game_move.to_string())
+                .add_attribute("game_state", game_state_json(updated_game_state)?))
+        }
+    };
+
+    res.map(|res| res.add_events(achievement_events))
+}
+
+/// Helper function for counting the hands in a row a player has won outright, up to the latest
+fn hand_streak(game_state: &GameState, is_player1: bool) -> u8 {
+    let won = if is_player1 {
+        GameResult::Player1Wins
+    } else {
+        GameResult::Player2Wins
+    };
+
+    game_state
+        .player1_moves
+        .iter()
+        .zip(game_state.player2_moves.iter())
+        .rev()
+        .take_while(|(player1_move, player2_move)| {
+            get_result((*player1_move).clone(), (*player2_move).clone()) == won
+        })
+        .count() as u8
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(_deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match (msg.id, msg.result) {
+        // The payouts already went through, only the trophy is missing
+        (TROPHY_MINT_REPLY_ID, ContractResult::Err(err)) => Ok(Response::new()
+            .add_attribute("action", "trophy_mint_failed")
+            .add_attribute("error", err)),
+        (HOOK_REPLY_ID, ContractResult::Err(err)) => Ok(Response::new()
+            .add_attribute("action", "hook_failed")
+            .add_attribute("error", err)),
+        _ => Err(ContractError::UnknownReplyId {}),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetGameByPlayer { player } => to_binary(&get_game_by_player(deps, player)?),
+        QueryMsg::GetGameByPlayers { player1, player2 } => {
+            to_binary(&get_game(deps, player1, player2)?)
+        }
+        QueryMsg::GetLeaderboard { start_after, limit } => {
+            to_binary(&get_leaderboard(deps, start_after, limit)?)
+        }
+        QueryMsg::GetOpenGames { start_after, limit } => {
+            to_binary(&get_open_games(deps, start_after, limit)?)
+        }
+        QueryMsg::GetGames { start_after, limit } => {
+            to_binary(&get_games(deps, start_after, limit)?)
+        }
+        QueryMsg::GetCurrentSeason {} => to_binary(&get_current_season(deps)?),
+        QueryMsg::GetSeasonLeaderboard { season_id, limit } => {
+            to_binary(&get_season_leaderboard(deps, season_id, limit)?)
+        }
+        QueryMsg::GetPrizePool {} => to_binary(&get_prize_pool(deps)?),
+        QueryMsg::GetSideBets { player1, player2 } => {
+            to_binary(&get_side_bets(deps, player1, player2)?)
+        }
+        QueryMsg::GetFees {} => to_binary(&get_fees(deps)?),
+        QueryMsg::GetReliability { player } => to_binary(&get_reliability(deps, player)?),
+        QueryMsg::GetReferralStats { referrer } => to_binary(&get_referral_stats(deps, referrer)?),
+        QueryMsg::GetPrizeDistribution { season_id } => {
+            to_binary(&get_prize_distribution(deps, season_id)?)
+        }
+        QueryMsg::GetTournament { tournament_id } => {
+            to_binary(&get_tournament(deps, tournament_id)?)
+        }
+        QueryMsg::GetTournaments { start_after, limit } => {
+            to_binary(&get_tournaments(deps, start_after, limit)?)
+        }
+        QueryMsg::GetLeague { league_id } => to_binary(&get_league(deps, league_id)?),
+        QueryMsg::GetLeagueStandings { league_id } => {
+            to_binary(&get_league_standings(deps, league_id)?)
+        }
+        QueryMsg::GetRematchOffer { player1, player2 } => {
+            to_binary(&get_rematch_offer(deps, player1, player2)?)
+        }
+        QueryMsg::GetSessionKey { player, delegate } => {
+            to_binary(&get_session_key(deps, player, delegate)?)
+        }
+        QueryMsg::GetSessionKeys { player } => to_binary(&get_session_keys(deps, player)?),
+        QueryMsg::GetHouse {} => to_binary(&get_house(deps)?),
+        QueryMsg::GetTreasury {} => to_binary(&get_treasury(deps, env)?),
+        QueryMsg::GetHouseGame { player } => to_binary(&get_house_game(deps, player)?),
+        QueryMsg::GetStreakLeaderboard { limit } => {
+            to_binary(&get_streak_leaderboard(deps, limit)?)
+        }
+        QueryMsg::GetAchievements { player } => to_binary(&get_achievements(deps, player)?),
+        QueryMsg::GetNftCollections {} => to_binary(&get_nft_collections(deps)?),
+        QueryMsg::GetPubkey { player } => to_binary(&get_pubkey(deps, player)?),
+        QueryMsg::GetRelayNonce { player } => to_binary(&get_relay_nonce(deps, player)?),
+        QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
+        QueryMsg::Hooks {} => to_binary(&HOOKS.query_hooks(deps)?),
+    }
+}
+
+pub fn get_game(
+    deps: Deps,
+    player1: String,
+    player2: String,
+) -> StdResult<GetGameByPlayersResponse> {
+    let maybe_game_state =
+        game_states().may_load(deps.storage, (player1.as_bytes(), player2.as_bytes()))?;
+
+    match maybe_game_state {
+        Some(game_state) => Ok(GetGameByPlayersResponse {
+            game: Some(game_state),
+        }),
+        None => Ok(GetGameByPlayersResponse { game: None }),
+    }
+}
+
+pub fn get_game_by_player(deps: Deps, player: String) -> StdResult<GetGameByPlayerResponse> {
+    let player_addr = deps.api.addr_validate(&player)?;
+
+    // check if there exists a game where player is player1
+    let maybe_game_tuple1 = game_states()
+        .idx
+        .player1
+        .item(deps.storage, player_addr.clone())?;
+
+    if let Some((_, game_state)) = maybe_game_tuple1 {
+        return Ok(GetGameByPlayerResponse {
+            game: Some(game_state),
+            waiting_for_opponent: false,
+        });
+    }
+
+    // check if there exists a game where player is player2
+    let maybe_game_tuple2 = game_states()
+        .idx
+        .player2
+        .item(deps.storage, player_addr.clone())?;
+
+    if let Some((_, game_state)) = maybe_game_tuple2 {
+        return Ok(GetGameByPlayerResponse {
+            game: Some(game_state),
+            waiting_for_opponent: false,
+        });
+    }
+
+    // check if the player is waiting for a game
+    let query_res = UNMATCHED_PLAYERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let player_is_unmatched = query_res
+        .iter()
+        .map(|(_, b)| b.address.clone())
+        .any(|x| x == player_addr);
+
+    if player_is_unmatched {
+        Ok(GetGameByPlayerResponse {
+            game: None,
+            waiting_for_opponent: true,
+        })
+    } else {
+        Ok(GetGameByPlayerResponse {
+            game: None,
+            waiting_for_opponent: false,
+        })
+    }
+}
+
+pub fn get_leaderboard(
+    deps: Deps,
+    _start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GetLeaderboardResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+
+    let zero_winnings_key = leaderboard()
+        .idx
+        .winnings
+        .index_key((I32Key::from(0), b"".to_vec()));
+    let most_negative_key = leaderboard()
+        .idx
+        .winnings
+        .index_key((I32Key::from(-2147483648), b"".to_vec()));
+
+    let res = leaderboard()
+        .idx
+        .winnings
+        .range(
+            deps.storage,
+            Some(Bound::inclusive(zero_winnings_key)),
+            Some(Bound::exclusive(most_negative_key)),
+            Order::Descending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let leaderboard = res.iter().map(|(_, b)| b.clone()).collect();
+
+    Ok(GetLeaderboardResponse { leaderboard })
+}
+
+pub fn get_streak_leaderboard(
+    deps: Deps,
+    limit: Option<u32>,
+) -> StdResult<GetStreakLeaderboardResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+
+    let res = leaderboard()
+        .idx
+        .best_win_streak
+        .range(deps.storage, None, None, Order::Descending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // Players who never won a game have no streak to show
+    let leaderboard = res
+        .into_iter()
+        .map(|(_, b)| b)
+        .filter(|b| b.best_win_streak > 0)
+        .collect();
+
+    Ok(GetStreakLeaderboardResponse { leaderboard })
+}
+
+pub fn get_achievements(deps: Deps, player: String) -> StdResult<GetAchievementsResponse> {
+    let player_addr = deps.api.addr_validate(&player)?;
+
+    let achievements = ACHIEVEMENTS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (_, achievement) = item?;
+            let unlocked_at = UNLOCKED_ACHIEVEMENTS.may_load(
+                deps.storage,
+                (player_addr.as_bytes(), achievement.id.as_bytes()),
+            )?;
+            Ok(unlocked_at.map(|unlocked_at| UnlockedAchievement {
+                achievement,
+                unlocked_at,
+            }))
+        })
+        .collect::<StdResult<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+
+    Ok(GetAchievementsResponse { achievements })
+}
+
+pub fn get_nft_collections(deps: Deps) -> StdResult<GetNftCollectionsResponse> {
+    let collections = NFT_COLLECTIONS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|key| Ok(Addr::unchecked(String::from_utf8(key)?)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(GetNftCollectionsResponse { collections })
+}
+
+pub fn get_pubkey(deps: Deps, player: String) -> StdResult<GetPubkeyResponse> {
+    let player = deps.api.addr_validate(&player)?;
+
+    Ok(GetPubkeyResponse {
+        pubkey: PUBKEYS.may_load(deps.storage, &player)?,
+    })
+}
+
+pub fn get_relay_nonce(deps: Deps, player: String) -> StdResult<GetRelayNonceResponse> {
+    let player = deps.api.addr_validate(&player)?;
+
+    Ok(GetRelayNonceResponse {
+        relay_nonce: RELAY_NONCES
+            .may_load(deps.storage, &player)?
+            .unwrap_or_default(),
+    })
+}
+
+pub fn get_house(deps: Deps) -> StdResult<GetHouseResponse> {
+    let chain = HOUSE_CHAIN.may_load(deps.storage)?;
+
+    Ok(GetHouseResponse {
+        config: HOUSE_CONFIG.may_load(deps.storage)?,
+        bankroll: HOUSE_BANKROLL.may_load(deps.storage)?.unwrap_or_default(),
+        exposure: HOUSE_EXPOSURE.may_load(deps.storage)?.unwrap_or_default(),
+        moves_available: chain.as_ref().map_or(0, |chain| chain.length - chain.dealt),
+        chain,
+    })
+}
+
+pub fn get_house_game(deps: Deps, player: String) -> StdResult<GetHouseGameResponse> {
+    let player = deps.api.addr_validate(&player)?;
+
+    Ok(GetHouseGameResponse {
+        game: HOUSE_GAMES.may_load(deps.storage, &player)?,
+    })
+}
+
+/// Adds up everything the contract owes from the running totals, so it stays cheap enough
+/// for the bankroll withdrawal to check solvency with
+pub fn get_treasury(deps: Deps, env: Env) -> StdResult<GetTreasuryResponse> {
+    let game_escrow = GAME_ESCROW.may_load(deps.storage)?.unwrap_or_default();
+    let queue_deposits = QUEUE_DEPOSITS.may_load(deps.storage)?.unwrap_or_default();
+    let competition_escrow = COMPETITION_ESCROW
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let prize_pool = PRIZE_POOL.may_load(deps.storage)?.unwrap_or_default();
+    let prize_distributions = PRIZE_DISTRIBUTION_TOTAL
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let accrued_fees = fee_coins(deps.storage, ACCRUED_FEES)?;
+    let side_bets = SIDE_BET_TOTAL.may_load(deps.storage)?.unwrap_or_default();
+    let penalty_bonds = PENALTY_BOND_TOTAL
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let referral_balances = REFERRAL_TOTAL.may_load(deps.storage)?.unwrap_or_default();
+    let house_bankroll = HOUSE_BANKROLL.may_load(deps.storage)?.unwrap_or_default();
+    let house_exposure = HOUSE_EXPOSURE.may_load(deps.storage)?.unwrap_or_default();
+
+    let mut liabilities = vec![];
+    for coins in [
+        &game_escrow,
+        &queue_deposits,
+        &competition_escrow,
+        &prize_pool,
+        &prize_distributions,
+        &accrued_fees,
+        &referral_balances,
+        &side_bets,
+        &penalty_bonds,
+        &house_bankroll,
+        &house_exposure,
+    ] {
+        add_coins(&mut liabilities, coins);
+    }
+    liabilities.retain(|coin| !coin.amount.is_zero());
+
+    let balance = deps.querier.query_all_balances(env.contract.address)?;
+    let solvent = sub_coins(&balance, &liabilities).is_some();
+
+    Ok(GetTreasuryResponse {
+        balance,
+        game_escrow,
+        queue_deposits,
+        competition_escrow,
+        prize_pool,
+        prize_distributions,
+        accrued_fees,
+        referral_balances,
+        side_bets,
+        penalty_bonds,
+        house_bankroll,
+        house_exposure,
+        liabilities,
+        solvent,
+    })
+}
+
+pub fn get_fees(deps: Deps) -> StdResult<GetFeesResponse> {
+    Ok(GetFeesResponse {
+        config: FEE_CONFIG.may_load(deps.storage)?,
+        accrued_fees: fee_coins(deps.storage, ACCRUED_FEES)?,
+        lifetime_fees: fee_coins(deps.storage, LIFETIME_FEES)?,
+    })
+}
+
+pub fn get_side_bets(
+    deps: Deps,
+    player1: String,
+    player2: String,
+) -> StdResult<GetSideBetsResponse> {
+    let game_id = game_states()
+        .load(deps.storage, (player1.as_bytes(), player2.as_bytes()))?
+        .id;
+
+    Ok(GetSideBetsResponse {
+        pool: SIDE_BET_POOLS
+            .may_load(deps.storage, game_id.into())?
+            .unwrap_or_default(),
+        bets: SIDE_BETS
+            .prefix(game_id.into())
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, side_bet)| side_bet))
+            .collect::<StdResult<Vec<SideBet>>>()?,
+    })
+}
+
+pub fn get_reliability(deps: Deps, player: String) -> StdResult<GetReliabilityResponse> {
+    let player = deps.api.addr_validate(&player)?;
+    let profile = leaderboard()
+        .may_load(deps.storage, player.as_bytes())?
+        .unwrap_or_else(|| new_user_profile(player.clone()));
+
+    Ok(GetReliabilityResponse {
+        games_played: profile.num_games_played,
+        games_abandoned: profile.games_abandoned,
+        games_forfeited: profile.games_forfeited,
+        reliability: reliability(&profile),
+        penalty_bond: PENALTY_BONDS
+            .may_load(deps.storage, &player)?
+            .unwrap_or_default(),
+    })
+}
+
+pub fn get_referral_stats(deps: Deps, referrer: String) -> StdResult<GetReferralStatsResponse> {
+    let referrer = deps.api.addr_validate(&referrer)?;
+    let stats = REFERRAL_STATS
+        .may_load(deps.storage, &referrer)?
+        .unwrap_or_default();
+
+    Ok(GetReferralStatsResponse {
+        referral_count: stats.referral_count,
+        earned: stats.earned,
+        balance: stats.balance,
+    })
+}
+
+/// Helper function for listing a per denom fee total as coins
+fn fee_coins(storage: &dyn Storage, fee_map: Map<&str, Uint128>) -> StdResult<Vec<Coin>> {
+    let mut coins = vec![];
+    for item in fee_map.range(storage, None, None, Order::Ascending) {
+        let (denom, amount) = item?;
+        if !amount.is_zero() {
+            coins.push(Coin {
+                denom: String::from_utf8(denom)?,
+                amount,
+            });
+        }
+    }
+    Ok(coins)
+}
+
+pub fn get_current_season(deps: Deps) -> StdResult<GetCurrentSeasonResponse> {
+    let season = match LATEST_SEASON_ID.may_load(deps.storage)? {
+        Some(season_id) => SEASONS.may_load(deps.storage, U32Key::new(season_id))?,
+        None => None,
+    };
+
+    Ok(GetCurrentSeasonResponse { season })
+}
+
+pub fn get_season_leaderboard(
+    deps: Deps,
+    season_id: u32,
+    limit: Option<u32>,
+) -> StdResult<GetSeasonLeaderboardResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+
+    let season = SEASONS.load(deps.storage, U32Key::new(season_id))?;
+
+    // Keys are relative to the season prefix
+    let zero_winnings_key = (I32Key::from(0), b"".to_vec()).joined_key();
+    let most_negative_key = (I32Key::from(-2147483648), b"".to_vec()).joined_key();
+
+    let res = season_leaderboard()
+        .idx
+        .winnings
+        .sub_prefix(U32Key::new(season_id))
+        .range(
+            deps.storage,
+            Some(Bound::inclusive(zero_winnings_key)),
+            Some(Bound::exclusive(most_negative_key)),
+            Order::Descending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let leaderboard = res.iter().map(|(_, b)| b.profile.clone()).collect();
+
+    Ok(GetSeasonLeaderboardResponse {
+        season,
+        leaderboard,
+    })
+}
+
+pub fn get_prize_pool(deps: Deps) -> StdResult<GetPrizePoolResponse> {
+    Ok(GetPrizePoolResponse {
+        prize_pool: PRIZE_POOL.may_load(deps.storage)?.unwrap_or_default(),
+        payout_curve: PAYOUT_CURVE.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+pub fn get_prize_distribution(
+    deps: Deps,
+    season_id: u32,
+) -> StdResult<GetPrizeDistributionResponse> {
+    Ok(GetPrizeDistributionResponse {
+        distribution: PRIZE_DISTRIBUTIONS.may_load(deps.storage, U32Key::new(season_id))?,
+    })
+}
+
+pub fn get_tournament(deps: Deps, tournament_id: u32) -> StdResult<GetTournamentResponse> {
+    Ok(GetTournamentResponse {
+        tournament: TOURNAMENTS.may_load(deps.storage, U32Key::new(tournament_id))?,
+    })
+}
+
+pub fn get_tournaments(
+    deps: Deps,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> StdResult<GetTournamentsResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+    let start = start_after.map(Bound::exclusive_int);
+
+    let res = TOURNAMENTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let tournaments = res.iter().map(|(_, b)| b.clone()).collect();
+
+    Ok(GetTournamentsResponse { tournaments })
+}
+
+pub fn get_league(deps: Deps, league_id: u32) -> StdResult<GetLeagueResponse> {
+    Ok(GetLeagueResponse {
+        league: LEAGUES.may_load(deps.storage, U32Key::new(league_id))?,
+    })
+}
+
+pub fn get_league_standings(deps: Deps, league_id: u32) -> StdResult<GetLeagueStandingsResponse> {
+    let league = LEAGUES.load(deps.storage, U32Key::new(league_id))?;
+
+    // Most points first, then most wins
+    let mut standings = league.standings;
+    standings.sort_by(|a, b| b.points.cmp(&a.points).then(b.won.cmp(&a.won)));
+
+    Ok(GetLeagueStandingsResponse {
+        status: league.status,
+        standings,
+    })
+}
+
+pub fn get_rematch_offer(
+    deps: Deps,
+    player1: String,
+    player2: String,
+) -> StdResult<GetRematchOfferResponse> {
+    Ok(GetRematchOfferResponse {
+        rematch_offer: REMATCH_OFFERS
+            .may_load(deps.storage, (player1.as_bytes(), player2.as_bytes()))?,
+    })
+}
+
+pub fn get_session_key(
+    deps: Deps,
+    player: String,
+    delegate: String,
+) -> StdResult<GetSessionKeyResponse> {
+    Ok(GetSessionKeyResponse {
+        session_key: SESSION_KEYS
+            .may_load(deps.storage, (player.as_bytes(), delegate.as_bytes()))?,
+    })
+}
+
+pub fn get_session_keys(deps: Deps, player: String) -> StdResult<GetSessionKeysResponse> {
+    let session_keys = SESSION_KEYS
+        .prefix(player.as_bytes())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, session_key)| session_key))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(GetSessionKeysResponse { session_keys })
+}
+
+pub fn get_open_games(
+    deps: Deps,
+    _start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GetOpenGamesResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+
+    let res = UNMATCHED_PLAYERS
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let open_games = res.iter().map(|(_, b)| b.clone()).collect();
+
+    Ok(GetOpenGamesResponse { open_games })
+}
+
+pub fn get_games(
+    deps: Deps,
+    _start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GetGamesResponse> {
+    let limit = limit.unwrap_or(10).min(30) as usize;
+
+    let res = game_states()
+        .range(deps.storage, None, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let game_states = res.iter().map(|(_, b)| b.clone()).collect();
+
+    Ok(GetGamesResponse { games: game_states })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msg::{ChannelState, RelayMoveMsg};
+    use crate::state::{LegacyGameState, LegacyUnmatchedPlayer};
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+        MOCK_CONTRACT_ADDR,
+    };
+    use cosmwasm_std::{coin, coins, from_slice, OwnedDeps, ReplyOn, StdError};
+
+    #[test]
+    fn test_leaderboard() {
+        // get deps
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        // instantiate smart contract
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        fn play_hand(
+            deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+            player1_name: String,
+            player2_name: String,
+            bet_amount: u128,
+        ) {
+            // define moves
+            let rock_move_hash = format!(
+                "{:x}",
+                Sha256::digest(format!("{}{}", GameMove::Rock, "1").as_bytes())
+            );
+            let paper_move_hash = format!(
+                "{:x}",
+                Sha256::digest(format!("{}{}", GameMove::Paper, "1").as_bytes())
+            );
+
+            let _scissor_move_hash = format!(
+                "{:x}",
+                Sha256::digest(format!("{}{}", GameMove::Scissors, "1").as_bytes())
+            );
+
+            // create players
+            let player1_funds = mock_info(&player1_name, &coins(bet_amount, "token"));
+            let player1 = mock_info(&player1_name, &coins(0, "token"));
+            let player2_funds = mock_info(&player2_name, &coins(bet_amount, "token"));
+            let player2 = mock_info(&player2_name, &coins(0, "token"));
+
+            // create start game messages
+            let join_game_message = ExecuteMsg::JoinGame {
+                num_hands_to_win: 1,
+                tie_break: None,
+                mode: None,
+                referrer: None,
+                reliable_only: None,
+            };
+
+            // player 1 join game
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                player1_funds,
+                join_game_message.clone(),
+            )
+            .unwrap();
+
+            // player 2 join game
+            execute(deps.as_mut(), mock_env(), player2_funds, join_game_message).unwrap();
+
+            let player1_commit_message1 = ExecuteMsg::CommitMove {
+                player1: player1_name.clone(),
+                player2: player2_name.clone(),
+                hashed_move: rock_move_hash,
+            };
+
+            let player2_commit_message1 = ExecuteMsg::CommitMove {
+                player1: player1_name.clone(),
+                player2: player2_name.clone(),
+                hashed_move: paper_move_hash,
+            };
+
+            // player 1 commit move
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                player1.clone(),
+                player1_commit_message1,
+            )
+            .unwrap();
+
+            // player 2 commit move
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                player2.clone(),
+                player2_commit_message1,
+            )
+            .unwrap();
+
+            let player1_reveal_message1 = ExecuteMsg::RevealMove {
+                player1: player1_name.clone(),
+                player2: player2_name.clone(),
+                game_move: GameMove::Rock,
+                nonce: String::from("1"),
+            };
+
+            let player2_reveal_message1 = ExecuteMsg::RevealMove {
+                player1: player1_name,
+                player2: player2_name,
+                game_move: GameMove::Paper,
+                nonce: String::from("1"),
+            };
+
+            // player 1 reveal move
+            execute(deps.as_mut(), mock_env(), player1, player1_reveal_message1).unwrap();
+
+            // player 2 reveal move
+            execute(deps.as_mut(), mock_env(), player2, player2_reveal_message1).unwrap();
+        }
+
+        play_hand(
+            &mut deps,
+            String::from("player1"),
+            String::from("player2"),
+            5,
+        );
+        play_hand(
+            &mut deps,
+            String::from("player3"),
+            String::from("player4"),
+            3,
+        );
+
+        // index_key() over UniqueIndex works.
+        // let age_key = (I32Key::from(-50), b"".to_vec());
+        let _min_winnings_key = leaderboard()
+            .idx
+            .winnings
+            .index_key((I32Key::from(-5), b"".to_vec()));
+        let _max_winnings_key = leaderboard()
+            .idx
+            .winnings
+            .index_key((I32Key::from(5), b"".to_vec()));
+        let zero_winnings_key = leaderboard()
+            .idx
+            .winnings
+            .index_key((I32Key::from(0), b"".to_vec()));
+        let most_negative_key = leaderboard()
+            .idx
+            .winnings
+            .index_key((I32Key::from(-2147483648), b"".to_vec()));
+
+        let leaderboard_res = leaderboard()
+            .idx
+            .winnings
+            .range(
+                deps.as_mut().storage,
+                Some(Bound::inclusive(zero_winnings_key.clone())),
+                Some(Bound::exclusive(most_negative_key.clone())),
+                Order::Descending,
+            )
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+
+        println!("Leaderboard Internal Res:");
+        println!("{:?}", leaderboard_res);
+
+        let leaderboard_res = leaderboard()
+            .idx
+            .winnings
+            .keys(
+                deps.as_mut().storage,
+                // None,
+                // Some(Bound::exclusive(max_winnings_key)),
+                Some(Bound::inclusive(zero_winnings_key)),
+                Some(Bound::exclusive(most_negative_key)),
+                // None,
+                Order::Descending,
+            )
+            .collect::<Vec<_>>();
+
+        println!("Leaderboard Keys:");
+        println!("{:?}", leaderboard_res);
+
+        println!("End test")
+    }
+
+    fn play_hand(
+        deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        player1_name: String,
+        player2_name: String,
+        bet_amount: u128,
+    ) {
+        // create players
+        let player1_funds = mock_info(&player1_name, &coins(bet_amount, "token"));
+        let player2_funds = mock_info(&player2_name, &coins(bet_amount, "token"));
+
+        // create start game messages
+        let join_game_message = ExecuteMsg::JoinGame {
+            num_hands_to_win: 1,
+            tie_break: None,
+            mode: None,
+            referrer: None,
+            reliable_only: None,
+        };
+
+        // player 1 join game
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            player1_funds,
+            join_game_message.clone(),
+        )
+        .unwrap();
+
+        // player 2 join game
+        execute(deps.as_mut(), mock_env(), player2_funds, join_game_message).unwrap();
+
+        // player 2 wins with paper
+        play_moves(
+            deps,
+            &player1_name,
+            &player2_name,
+            GameMove::Rock,
+            GameMove::Paper,
+        );
+    }
+
+    fn play_moves(
+        deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        player1_name: &str,
+        player2_name: &str,
+        player1_move: GameMove,
+        player2_move: GameMove,
+    ) -> Response {
+        play_moves_at(
+            deps,
+            mock_env(),
+            player1_name,
+            player2_name,
+            player1_move,
+            player2_move,
+        )
+    }
+
+    fn play_moves_at(
+        deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        env: Env,
+        player1_name: &str,
+        player2_name: &str,
+        player1_move: GameMove,
+        player2_move: GameMove,
+    ) -> Response {
+        // define moves
+        let player1_move_hash = format!(
+            "{:x}",
+            Sha256::digest(format!("{}{}", player1_move, "1").as_bytes())
+        );
+        let player2_move_hash = format!(
+            "{:x}",
+            Sha256::digest(format!("{}{}", player2_move, "1").as_bytes())
+        );
+
+        // create players
+        let player1 = mock_info(player1_name, &coins(0, "token"));
+        let player2 = mock_info(player2_name, &coins(0, "token"));
+
+        let player1_commit_message1 = ExecuteMsg::CommitMove {
+            player1: player1_name.to_string(),
+            player2: player2_name.to_string(),
+            hashed_move: player1_move_hash,
+        };
+
+        let player2_commit_message1 = ExecuteMsg::CommitMove {
+            player1: player1_name.to_string(),
+            player2: player2_name.to_string(),
+            hashed_move: player2_move_hash,
+        };
+
+        // player 1 commit move
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            player1.clone(),
+            player1_commit_message1,
+        )
+        .unwrap();
+
+        // player 2 commit move
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            player2.clone(),
+            player2_commit_message1,
+        )
+        .unwrap();
+
+        let player1_reveal_message1 = ExecuteMsg::RevealMove {
+            player1: player1_name.to_string(),
+            player2: player2_name.to_string(),
+            game_move: player1_move,
+            nonce: String::from("1"),
+        };
+
+        let player2_reveal_message1 = ExecuteMsg::RevealMove {
+            player1: player1_name.to_string(),
+            player2: player2_name.to_string(),
+            game_move: player2_move,
+            nonce: String::from("1"),
+        };
+
+        // player 1 reveal move
+        execute(deps.as_mut(), env.clone(), player1, player1_reveal_message1).unwrap();
+
+        // player 2 reveal move
+        execute(deps.as_mut(), env.clone(), player2, player2_reveal_message1).unwrap()
+    }
+
+    // Checks the running treasury totals against a rebuild from the records
+    fn assert_treasury_totals(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>) {
+        let treasury = get_treasury(deps.as_ref(), mock_env()).unwrap();
+        seed_treasury_totals(deps.as_mut().storage).unwrap();
+        assert_eq!(get_treasury(deps.as_ref(), mock_env()).unwrap(), treasury);
+    }
+
+    #[test]
+    fn test_season_leaderboard() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("creator")),
+            trophy_contract: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // games before the season don't count towards it
+        play_hand(
+            &mut deps,
+            String::from("player1"),
+            String::from("player2"),
+            5,
+        );
+
+        // only the admin can start a season
+        let start_season_message = ExecuteMsg::StartSeason {
+            end_time: mock_env().block.time.nanos() + 1_000,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            start_season_message.clone(),
+        )
+        .unwrap_err();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            start_season_message,
+        )
+        .unwrap();
+
+        play_hand(
+            &mut deps,
+            String::from("player3"),
+            String::from("player4"),
+            3,
+        );
+
+        // end the season, later games don't change its standings
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::EndSeason {},
+        )
+        .unwrap();
+
+        play_hand(
+            &mut deps,
+            String::from("player5"),
+            String::from("player6"),
+            7,
+        );
+
+        let res = get_season_leaderboard(deps.as_ref(), 1, None).unwrap();
+        assert!(res.season.ended);
+        assert_eq!(res.leaderboard.len(), 1);
+        assert_eq!(res.leaderboard[0].address, Addr::unchecked("player4"));
+        assert_eq!(res.leaderboard[0].winnings, 3);
+
+        // the all time leaderboard still has every game
+        let res = get_leaderboard(deps.as_ref(), None, None).unwrap();
+        assert_eq!(res.leaderboard.len(), 3);
+    }
+
+    #[test]
+    fn test_distribute_prizes() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("creator")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let admin = mock_info("creator", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin.clone(),
+            ExecuteMsg::StartSeason {
+                end_time: mock_env().block.time.nanos() + 1_000,
+            },
+        )
+        .unwrap();
+
+        // player2 wins 5, player4 wins 3
+        play_hand(
+            &mut deps,
+            String::from("player1"),
+            String::from("player2"),
+            5,
+        );
+        play_hand(
+            &mut deps,
+            String::from("player3"),
+            String::from("player4"),
+            3,
+        );
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sponsor", &coins(100, "token")),
+            ExecuteMsg::FundPrizePool {},
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin.clone(),
+            ExecuteMsg::SetPayoutCurve {
+                payout_curve: vec![50, 30, 20],
+            },
+        )
+        .unwrap();
+
+        // can't pay out a season that is still running
+        let distribute_message = ExecuteMsg::DistributePrizes {
+            season_id: 1,
+            limit: Some(1),
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin.clone(),
+            distribute_message.clone(),
+        )
+        .unwrap_err();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            admin.clone(),
+            ExecuteMsg::EndSeason {},
+        )
+        .unwrap();
+
+        // first batch pays first place
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            admin.clone(),
+            distribute_message.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player2"),
+                amount: coins(50, "token"),
+            })
+        );
+
+        // the prizes still owed stay a liability between batches
+        let treasury = get_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert!(treasury.prize_pool.is_empty());
+        assert_eq!(treasury.prize_distributions, coins(50, "token"));
+        assert_treasury_totals(&mut deps);
+
+        // second batch pays second place
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            admin.clone(),
+            distribute_message.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player4"),
+                amount: coins(30, "token"),
+            })
+        );
+        let treasury = get_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(treasury.prize_distributions, coins(20, "token"));
+        assert_treasury_totals(&mut deps);
+
+        // third batch runs out of players and finishes the distribution
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            admin.clone(),
+            distribute_message.clone(),
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        execute(deps.as_mut(), mock_env(), admin, distribute_message).unwrap_err();
+
+        // the unclaimed third place share goes back into the pool
+        let res = get_prize_pool(deps.as_ref()).unwrap();
+        assert_eq!(res.prize_pool, coins(20, "token"));
+        let treasury = get_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert!(treasury.prize_distributions.is_empty());
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_tournament() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::CreateTournament {
+                size: 4,
+                buy_in: coins(10, "token"),
+                num_hands_to_win: 1,
+                registration_deadline: mock_env().block.time.nanos() + 1_000,
+                payout_curve: Some(vec![70, 30]),
+            },
+        )
+        .unwrap();
+
+        // registering needs the exact buy in
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(5, "token")),
+            ExecuteMsg::RegisterForTournament { tournament_id: 1 },
+        )
+        .unwrap_err();
+
+        for player in ["player1", "player2", "player3", "player4"].iter() {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(10, "token")),
+                ExecuteMsg::RegisterForTournament { tournament_id: 1 },
+            )
+            .unwrap();
+        }
+
+        // tournament players can't queue for other games
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(10, "token")),
+            ExecuteMsg::JoinGame {
+                num_hands_to_win: 1,
+                tie_break: None,
+                mode: None,
+                referrer: None,
+                reliable_only: None,
+            },
+        )
+        .unwrap_err();
+
+        // round one is played without paying out
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Paper,
+        );
+        assert!(res.messages.is_empty());
+        play_moves(
+            &mut deps,
+            "player3",
+            "player4",
+            GameMove::Paper,
+            GameMove::Rock,
+        );
+
+        // the winners meet in the final
+        let res = get_game(
+            deps.as_ref(),
+            String::from("player2"),
+            String::from("player3"),
+        )
+        .unwrap();
+        assert!(res.game.is_some());
+
+        let res = play_moves(
+            &mut deps,
+            "player2",
+            "player3",
+            GameMove::Scissors,
+            GameMove::Paper,
+        );
+        assert_eq!(
+            res.messages
+                .into_iter()
+                .map(|sub_msg| sub_msg.msg)
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("player2"),
+                    amount: coins(28, "token"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("player3"),
+                    amount: coins(12, "token"),
+                }),
+            ]
+        );
+
+        let res = get_tournament(deps.as_ref(), 1).unwrap();
+        assert_eq!(res.tournament.unwrap().status, TournamentStatus::Complete);
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_league() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::CreateLeague {
+                participants: vec![
+                    String::from("player1"),
+                    String::from("player2"),
+                    String::from("player3"),
+                ],
+                entry_fee: coins(10, "token"),
+                num_hands_to_win: 1,
+                join_deadline: mock_env().block.time.nanos() + 1_000,
+                round_duration: 1_000,
+            },
+        )
+        .unwrap();
+
+        // only invited players can join
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player4", &coins(10, "token")),
+            ExecuteMsg::JoinLeague { league_id: 1 },
+        )
+        .unwrap_err();
+
+        for player in ["player1", "player2", "player3"].iter() {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(10, "token")),
+                ExecuteMsg::JoinLeague { league_id: 1 },
+            )
+            .unwrap();
+        }
+
+        // round one, player1 sits out
+        play_moves(
+            &mut deps,
+            "player2",
+            "player3",
+            GameMove::Rock,
+            GameMove::Paper,
+        );
+
+        // round two, player2 sits out
+        play_moves(
+            &mut deps,
+            "player1",
+            "player3",
+            GameMove::Paper,
+            GameMove::Rock,
+        );
+
+        // round three never gets played
+        let timeout_message = ExecuteMsg::TimeoutLeagueRound { league_id: 1 };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            timeout_message.clone(),
+        )
+        .unwrap_err();
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(1);
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("player1", &[]),
+            timeout_message,
+        )
+        .unwrap();
+
+        // player1 and player3 share first place
+        assert_eq!(
+            res.messages
+                .into_iter()
+                .map(|sub_msg| sub_msg.msg)
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("player1"),
+                    amount: coins(15, "token"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("player3"),
+                    amount: coins(15, "token"),
+                }),
+            ]
+        );
+
+        let res = get_league_standings(deps.as_ref(), 1).unwrap();
+        assert_eq!(res.status, LeagueStatus::Complete);
+        assert_eq!(
+            res.standings
+                .iter()
+                .map(|standing| standing.points)
+                .collect::<Vec<_>>(),
+            vec![3, 3, 0]
+        );
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_rematch() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        play_hand(
+            &mut deps,
+            String::from("player1"),
+            String::from("player2"),
+            5,
+        );
+
+        // the finished game leaves an offer behind
+        let res = get_rematch_offer(
+            deps.as_ref(),
+            String::from("player1"),
+            String::from("player2"),
+        )
+        .unwrap();
+        assert_eq!(res.rematch_offer.unwrap().offered_by, None);
+
+        // nobody has offered yet so there's nothing to accept
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(5, "token")),
+            ExecuteMsg::AcceptRematch {
+                opponent: String::from("player2"),
+            },
+        )
+        .unwrap_err();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &coins(5, "token")),
+            ExecuteMsg::OfferRematch {
+                opponent: String::from("player1"),
+            },
+        )
+        .unwrap();
+
+        // accepting needs the same bet
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(3, "token")),
+            ExecuteMsg::AcceptRematch {
+                opponent: String::from("player2"),
+            },
+        )
+        .unwrap_err();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(5, "token")),
+            ExecuteMsg::AcceptRematch {
+                opponent: String::from("player2"),
+            },
+        )
+        .unwrap();
+
+        let res = get_game(
+            deps.as_ref(),
+            String::from("player1"),
+            String::from("player2"),
+        )
+        .unwrap();
+        let game = res.game.unwrap();
+        assert_eq!(game.player1_stake, Asset::Coins(coins(5, "token")));
+        assert_eq!(game.num_hands_to_win, 1);
+
+        let res = get_rematch_offer(
+            deps.as_ref(),
+            String::from("player1"),
+            String::from("player2"),
+        )
+        .unwrap();
+        assert_eq!(res.rematch_offer, None);
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_tie_break() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let refund_message = ExecuteMsg::JoinGame {
+            num_hands_to_win: 3,
+            tie_break: Some(TieBreak {
+                max_ties: 2,
+                policy: TiePolicy::Refund,
+            }),
+            mode: None,
+            referrer: None,
+            reliable_only: None,
+        };
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(5, "token")),
+            refund_message.clone(),
+        )
+        .unwrap();
+
+        // a game without a tie cap doesn't get matched against it
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player3", &coins(5, "token")),
+            ExecuteMsg::JoinGame {
+                num_hands_to_win: 3,
+                tie_break: None,
+                mode: None,
+                referrer: None,
+                reliable_only: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2].value, "false");
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &coins(5, "token")),
+            refund_message,
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2].value, "true");
+
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Rock,
+        );
+        assert_eq!(res.messages.len(), 0);
+
+        // hitting the cap refunds both players
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Rock,
+        );
+        assert_eq!(
+            res.messages
+                .iter()
+                .map(|msg| msg.msg.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("player1"),
+                    amount: coins(5, "token"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("player2"),
+                    amount: coins(5, "token"),
+                }),
+            ]
+        );
+
+        let res = get_game(
+            deps.as_ref(),
+            String::from("player1"),
+            String::from("player2"),
+        )
+        .unwrap();
+        assert_eq!(res.game, None);
+
+        // refunded games don't count
+        let res = get_leaderboard(deps.as_ref(), None, None).unwrap();
+        assert_eq!(res.leaderboard.len(), 0);
+
+        let sudden_death_message = ExecuteMsg::JoinGame {
+            num_hands_to_win: 3,
+            tie_break: Some(TieBreak {
+                max_ties: 1,
+                policy: TiePolicy::SuddenDeath,
+            }),
+            mode: None,
+            referrer: None,
+            reliable_only: None,
+        };
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player4", &coins(5, "token")),
+            sudden_death_message.clone(),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player5", &coins(5, "token")),
+            sudden_death_message,
+        )
+        .unwrap();
+
+        play_moves(
+            &mut deps,
+            "player4",
+            "player5",
+            GameMove::Rock,
+            GameMove::Rock,
+        );
+
+        // past the cap a tied hand goes to whoever revealed first
+        let res = play_moves(
+            &mut deps,
+            "player4",
+            "player5",
+            GameMove::Rock,
+            GameMove::Rock,
+        );
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player4"),
+                amount: coins(5, "token"),
+            })
+        );
+
+        let res = get_game(
+            deps.as_ref(),
+            String::from("player4"),
+            String::from("player5"),
+        )
+        .unwrap();
+        assert_eq!(res.game, None);
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // player2 is up 5 from an earlier game
+        play_hand(
+            &mut deps,
+            String::from("player1"),
+            String::from("player2"),
+            5,
+        );
+
+        let join_game_message = ExecuteMsg::JoinGame {
+            num_hands_to_win: 1,
+            tie_break: Some(TieBreak {
+                max_ties: 1,
+                policy: TiePolicy::SplitPot,
+            }),
+            mode: None,
+            referrer: None,
+            reliable_only: None,
+        };
+
+        for player in ["player1", "player2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(5, "token")),
+                join_game_message.clone(),
+            )
+            .unwrap();
+        }
+
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Paper,
+            GameMove::Paper,
+        );
+
+        // both players get their bet back
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player2"),
+                amount: coins(5, "token"),
+            })
+        );
+
+        let player1_profile = leaderboard()
+            .load(&deps.storage, "player1".as_bytes())
+            .unwrap();
+        let player2_profile = leaderboard()
+            .load(&deps.storage, "player2".as_bytes())
+            .unwrap();
+
+        // the draw counts as played, but nobody wins it
+        assert_eq!(player1_profile.num_games_played, 2);
+        assert_eq!(player1_profile.num_games_drawn, 1);
+        assert_eq!(player1_profile.num_games_won, 0);
+        assert_eq!(player1_profile.winnings, -5);
+        assert_eq!(player2_profile.num_games_played, 2);
+        assert_eq!(player2_profile.num_games_drawn, 1);
+        assert_eq!(player2_profile.num_games_won, 1);
+        assert_eq!(player2_profile.winnings, 5);
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_streaks() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // player2 wins twice in a row
+        for _ in 0..2 {
+            play_hand(
+                &mut deps,
+                String::from("player1"),
+                String::from("player2"),
+                5,
+            );
+        }
+
+        for player in ["player1", "player2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(5, "token")),
+                ExecuteMsg::JoinGame {
+                    num_hands_to_win: 1,
+                    tie_break: None,
+                    mode: None,
+                    referrer: None,
+                    reliable_only: None,
+                },
+            )
+            .unwrap();
+        }
+
+        // player1 breaks the streak and starts their own
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Paper,
+            GameMove::Rock,
+        );
+        assert_eq!(
+            res.events
+                .into_iter()
+                .filter(|event| event.ty == "rps_win_streak")
+                .collect::<Vec<_>>(),
+            vec![Event::new("rps_win_streak")
+                .add_attribute("schema_version", events::EVENT_SCHEMA_VERSION)
+                .add_attribute("player", "player1")
+                .add_attribute("best_win_streak", "1")]
+        );
+
+        let player1_profile = leaderboard()
+            .load(&deps.storage, "player1".as_bytes())
+            .unwrap();
+        let player2_profile = leaderboard()
+            .load(&deps.storage, "player2".as_bytes())
+            .unwrap();
+        assert_eq!(player1_profile.current_streak, 1);
+        assert_eq!(player1_profile.last_result, Some(PlayerResult::Won));
+        assert_eq!(player2_profile.current_streak, -1);
+        assert_eq!(player2_profile.best_win_streak, 2);
+        assert_eq!(player2_profile.last_result, Some(PlayerResult::Lost));
+
+        let res = get_streak_leaderboard(deps.as_ref(), None).unwrap();
+        assert_eq!(
+            res.leaderboard
+                .iter()
+                .map(|profile| (profile.address.as_str(), profile.best_win_streak))
+                .collect::<Vec<_>>(),
+            vec![("player2", 2), ("player1", 1)]
+        );
+    }
+
+    #[test]
+    fn test_achievements() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("creator")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let add_achievement_message = ExecuteMsg::AddAchievement {
+            achievement: Achievement {
+                id: String::from("paper_cut"),
+                name: String::from("Paper Cut"),
+                description: String::from("Win a game playing only Paper"),
+                criteria: AchievementCriteria::SingleMoveWin {
+                    game_move: GameMove::Paper,
+                },
+            },
+        };
+
+        // only the admin can add achievements
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            add_achievement_message.clone(),
+        )
+        .unwrap_err();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            add_achievement_message.clone(),
+        )
+        .unwrap();
+
+        // ids can't be reused
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            add_achievement_message,
+        )
+        .unwrap_err();
+
+        let join_game_message = ExecuteMsg::JoinGame {
+            num_hands_to_win: 1,
+            tie_break: None,
+            mode: None,
+            referrer: None,
+            reliable_only: None,
+        };
+
+        for player in ["player1", "player2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(5, "token")),
+                join_game_message.clone(),
+            )
+            .unwrap();
+        }
+
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Paper,
+        );
+        let unlocked = res
+            .events
+            .iter()
+            .filter(|event| event.ty == "rps_achievement_unlocked")
+            .map(|event| event.attributes[2].value.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(unlocked, vec!["first_win", "paper_cut"]);
+
+        let res = get_achievements(deps.as_ref(), String::from("player2")).unwrap();
+        assert_eq!(res.achievements.len(), 2);
+        let res = get_achievements(deps.as_ref(), String::from("player1")).unwrap();
+        assert_eq!(res.achievements.len(), 0);
+
+        for player in ["player1", "player2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(5, "token")),
+                join_game_message.clone(),
+            )
+            .unwrap();
+        }
+
+        // player2 is top of the leaderboard going into this one
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Scissors,
+        );
+        let unlocked = res
+            .events
+            .iter()
+            .filter(|event| event.ty == "rps_achievement_unlocked")
+            .map(|event| event.attributes[2].value.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(unlocked, vec!["first_win", "giant_slayer", "rock_solid"]);
+    }
+
+    #[test]
+    fn test_trophies() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("creator")),
+            trophy_contract: Some(String::from("trophies")),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::StartSeason {
+                end_time: mock_env().block.time.nanos() + 1_000,
+            },
+        )
+        .unwrap();
+
+        play_hand(
+            &mut deps,
+            String::from("player1"),
+            String::from("player2"),
+            5,
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::EndSeason {},
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::SetPayoutCurve {
+                payout_curve: vec![100],
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &coins(10, "token")),
+            ExecuteMsg::FundPrizePool {},
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::DistributePrizes {
+                season_id: 1,
+                limit: None,
+            },
+        )
+        .unwrap();
+
+        // the season leader gets a trophy with their prize, without the mint being able to
+        // fail the payout
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player2"),
+                amount: coins(10, "token"),
+            })
+        );
+        assert_eq!(res.messages[1].id, TROPHY_MINT_REPLY_ID);
+        assert_eq!(res.messages[1].reply_on, ReplyOn::Error);
+        if let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) = &res.messages[1].msg
+        {
+            assert_eq!(contract_addr, "trophies");
+            let mint_msg = match from_binary(msg).unwrap() {
+                Cw721ExecuteMsg::Mint(mint_msg) => mint_msg,
+                _ => panic!("expected a mint message"),
+            };
+            assert_eq!(mint_msg.token_id, "season-1");
+            assert_eq!(mint_msg.owner, "player2");
+            assert_eq!(
+                mint_msg.extension.attributes[0],
+                trait_attribute("record", String::from("1-0-0"))
+            );
+        } else {
+            panic!("expected a mint message");
+        }
+
+        // a failed mint is swallowed
+        let res = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: TROPHY_MINT_REPLY_ID,
+                result: ContractResult::Err(String::from("token_id already claimed")),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0].value, "trophy_mint_failed");
+    }
+
+    #[test]
+    fn test_nft_wager() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("creator")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let add_collection_message = ExecuteMsg::AddNftCollection {
+            collection: String::from("collection"),
+        };
+
+        // only the admin can whitelist collections
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            add_collection_message.clone(),
+        )
+        .unwrap_err();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            add_collection_message,
+        )
+        .unwrap();
+
+        let receive_nft_message = |player: &str, token_id: &str| {
+            ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: player.to_string(),
+                token_id: token_id.to_string(),
+                msg: to_binary(&ReceiveNftMsg::JoinGame {
+                    num_hands_to_win: 1,
+                    tie_break: None,
+                    mode: None,
+                })
+                .unwrap(),
+            })
+        };
+
+        // tokens from other collections aren't accepted
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("other_collection", &[]),
+            receive_nft_message("player1", "1"),
+        )
+        .unwrap_err();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("collection", &[]),
+            receive_nft_message("player1", "1"),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2].value, "false");
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("collection", &[]),
+            receive_nft_message("player2", "2"),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2].value, "true");
+
+        let res = get_game(
+            deps.as_ref(),
+            String::from("player1"),
+            String::from("player2"),
+        )
+        .unwrap();
+        let game = res.game.unwrap();
+        assert_eq!(
+            game.player1_stake,
+            Asset::Nft(Nft {
+                collection: Addr::unchecked("collection"),
+                token_id: String::from("1"),
+            })
+        );
+        assert_eq!(
+            game.player2_stake,
+            Asset::Nft(Nft {
+                collection: Addr::unchecked("collection"),
+                token_id: String::from("2"),
+            })
+        );
+
+        // the winner gets both tokens
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Paper,
+        );
+        let transfers = res
+            .messages
+            .iter()
+            .map(|msg| match &msg.msg {
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr, msg, ..
+                }) => (contract_addr.clone(), from_binary(msg).unwrap()),
+                _ => panic!("expected an NFT transfer"),
+            })
+            .collect::<Vec<(String, Cw721ExecuteMsg)>>();
+        assert_eq!(
+            transfers,
+            vec![
+                (
+                    String::from("collection"),
+                    Cw721ExecuteMsg::TransferNft {
+                        recipient: String::from("player2"),
+                        token_id: String::from("1"),
+                    }
+                ),
+                (
+                    String::from("collection"),
+                    Cw721ExecuteMsg::TransferNft {
+                        recipient: String::from("player2"),
+                        token_id: String::from("2"),
+                    }
+                ),
+            ]
+        );
+
+        // there's no coin bet to rematch for
+        let res = get_rematch_offer(
+            deps.as_ref(),
+            String::from("player1"),
+            String::from("player2"),
+        )
+        .unwrap();
+        assert_eq!(res.rematch_offer, None);
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_hooks() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("creator")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let add_hook_message = ExecuteMsg::AddHook {
+            addr: String::from("rewards"),
+        };
+
+        // only the admin can add hooks
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            add_hook_message.clone(),
+        )
+        .unwrap_err();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            add_hook_message,
+        )
+        .unwrap();
+
+        let hooks = HOOKS.query_hooks(deps.as_ref()).unwrap();
+        assert_eq!(hooks.hooks, vec![String::from("rewards")]);
+
+        let hook_message = |event: GameEvent| {
+            SubMsg::reply_on_error(
+                GameEventHookMsg { event }
+                    .into_cosmos_msg("rewards")
+                    .unwrap(),
+                HOOK_REPLY_ID,
+            )
+        };
+
+        let join_game_message = ExecuteMsg::JoinGame {
+            num_hands_to_win: 1,
+            tie_break: None,
+            mode: None,
+            referrer: None,
+            reliable_only: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(5, "token")),
+            join_game_message.clone(),
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &coins(5, "token")),
+            join_game_message,
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![hook_message(GameEvent::GameCreated {
+                player1: Addr::unchecked("player1"),
+                player2: Addr::unchecked("player2"),
+                bet_amount: coins(5, "token"),
+                num_hands_to_win: 1,
+            })]
+        );
+
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Paper,
+        );
+        let hook_messages = res
+            .messages
+            .into_iter()
+            .filter(|msg| msg.id == HOOK_REPLY_ID)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            hook_messages,
+            vec![
+                hook_message(GameEvent::HandResolved {
+                    player1: Addr::unchecked("player1"),
+                    player2: Addr::unchecked("player2"),
+                    player1_move: GameMove::Rock,
+                    player2_move: GameMove::Paper,
+                    result: GameResult::Player2Wins,
+                }),
+                hook_message(GameEvent::GameEnded {
+                    player1: Addr::unchecked("player1"),
+                    player2: Addr::unchecked("player2"),
+                    result: GameResult::Player2Wins,
+                }),
+            ]
+        );
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::RemoveHook {
+                addr: String::from("rewards"),
+            },
+        )
+        .unwrap();
+        assert!(HOOKS.query_hooks(deps.as_ref()).unwrap().hooks.is_empty());
+
+        // a hook failing doesn't fail the game
+        let res = reply(
+            deps.as_mut(),
+            mock_env(),
+            Reply {
+                id: HOOK_REPLY_ID,
+                result: ContractResult::Err(String::from("out of gas")),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0].value, "hook_failed");
+    }
+
+    #[test]
+    fn test_events() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let attribute = |event: &Event, key: &str| {
+            event
+                .attributes
+                .iter()
+                .find(|attribute| attribute.key == key)
+                .map(|attribute| attribute.value.clone())
+        };
+
+        let join_game_message = ExecuteMsg::JoinGame {
+            num_hands_to_win: 1,
+            tie_break: None,
+            mode: None,
+            referrer: None,
+            reliable_only: None,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(5, "token")),
+            join_game_message.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty, "rps_player_queued");
+        assert_eq!(
+            attribute(&res.events[0], "schema_version"),
+            Some(String::from(events::EVENT_SCHEMA_VERSION))
+        );
+        assert_eq!(
+            attribute(&res.events[0], "bet"),
+            Some(String::from("5token"))
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &coins(5, "token")),
+            join_game_message.clone(),
+        )
+        .unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty, "rps_game_created");
+        assert_eq!(
+            attribute(&res.events[0], "game_id"),
+            Some(String::from("1"))
+        );
+        assert_eq!(
+            attribute(&res.events[0], "player1"),
+            Some(String::from("player1"))
+        );
+        assert_eq!(
+            attribute(&res.events[0], "player2"),
+            Some(String::from("player2"))
+        );
+
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Paper,
+        );
+        let event_types = res
+            .events
+            .iter()
+            .map(|event| event.ty.as_str())
+            .filter(|ty| ty.starts_with("rps_"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            event_types,
+            vec![
+                "rps_move_revealed",
+                "rps_hand_resolved",
+                "rps_win_streak",
+                "rps_achievement_unlocked",
+                "rps_game_ended"
+            ]
+        );
+        let hand_resolved = &res.events[1];
+        assert_eq!(
+            attribute(hand_resolved, "player1_move"),
+            Some(String::from("Rock"))
+        );
+        assert_eq!(
+            attribute(hand_resolved, "result"),
+            Some(String::from("player2_wins"))
+        );
+        assert_eq!(
+            attribute(hand_resolved, "player2_hands_won"),
+            Some(String::from("1"))
+        );
+        let game_ended = res
+            .events
+            .iter()
+            .find(|event| event.ty == "rps_game_ended")
+            .unwrap();
+        assert_eq!(attribute(game_ended, "game_id"), Some(String::from("1")));
+
+        // the players attribute keeps both addresses apart
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attribute| attribute.key == "players" && attribute.value == "player1,player2"));
+
+        // game ids keep counting up
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player3", &coins(5, "token")),
+            join_game_message.clone(),
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player4", &coins(5, "token")),
+            join_game_message.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            attribute(&res.events[0], "game_id"),
+            Some(String::from("2"))
+        );
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player5", &coins(5, "token")),
+            join_game_message,
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player5", &[]),
+            ExecuteMsg::LeaveWaitingQueue {},
+        )
+        .unwrap();
+        assert_eq!(res.events[0].ty, "rps_player_left_queue");
+        assert_eq!(
+            attribute(&res.events[0], "player"),
+            Some(String::from("player5"))
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let join_game_message = ExecuteMsg::JoinGame {
+            num_hands_to_win: 1,
+            tie_break: None,
+            mode: None,
+            referrer: None,
+            reliable_only: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(5, "token")),
+            join_game_message.clone(),
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &coins(5, "token")),
+            join_game_message,
+        )
+        .unwrap();
+
+        // the game_state attribute is still plain json the frontend can read
+        let game_state_attribute = res
+            .attributes
+            .iter()
+            .find(|attribute| attribute.key == "game_state")
+            .unwrap();
+        let game_state: GameState = from_slice(game_state_attribute.value.as_bytes()).unwrap();
+        assert_eq!(
+            game_state,
+            game_states()
+                .load(deps.as_ref().storage, (b"player1", b"player2"))
+                .unwrap()
+        );
+
+        // serde_json bloats the wasm, keep it out of the contract dependencies
+        let manifest = include_str!("../Cargo.toml");
+        let dependencies = manifest
+            .split("[dependencies]")
+            .nth(1)
+            .unwrap()
+            .split("\n[")
+            .next()
+            .unwrap();
+        assert!(!dependencies.contains("serde_json"));
+    }
+
+    #[test]
+    fn test_blitz() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // blitz games can't have a tie break
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(5, "token")),
+            ExecuteMsg::JoinGame {
+                num_hands_to_win: 2,
+                tie_break: Some(TieBreak {
+                    max_ties: 1,
+                    policy: TiePolicy::SplitPot,
+                }),
+                mode: Some(GameMode::Blitz),
+                referrer: None,
+                reliable_only: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTieBreak {}));
+
+        let blitz_message = ExecuteMsg::JoinGame {
+            num_hands_to_win: 2,
+            tie_break: None,
+            mode: Some(GameMode::Blitz),
+            referrer: None,
+            reliable_only: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(5, "token")),
+            blitz_message.clone(),
+        )
+        .unwrap();
+
+        // classic and blitz players aren't matched against each other
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player3", &coins(5, "token")),
+            ExecuteMsg::JoinGame {
+                num_hands_to_win: 2,
+                tie_break: None,
+                mode: None,
+                referrer: None,
+                reliable_only: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2].value, "false");
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &coins(5, "token")),
+            blitz_message,
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2].value, "true");
+
+        // player1 takes the first two hands, so the third is never played
+        let player1_moves = vec![GameMove::Rock, GameMove::Paper, GameMove::Paper];
+        let player2_moves = vec![GameMove::Scissors, GameMove::Rock, GameMove::Rock];
+        let commitment = |game_moves: &[GameMove], nonce: &str| {
+            format!(
+                "{:x}",
+                Sha256::digest(format!("{}{}", moves_to_string(game_moves), nonce).as_bytes())
+            )
+        };
+
+        for (player, game_moves) in [("player1", &player1_moves), ("player2", &player2_moves)] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &[]),
+                ExecuteMsg::CommitMove {
+                    player1: String::from("player1"),
+                    player2: String::from("player2"),
+                    hashed_move: commitment(game_moves, "1"),
+                },
+            )
+            .unwrap();
+        }
+
+        // hands can't be revealed one by one
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            ExecuteMsg::RevealMove {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                game_move: GameMove::Rock,
+                nonce: String::from("1"),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::WrongGameMode {}));
+
+        let reveal_message = |game_moves: &[GameMove], nonce: &str| ExecuteMsg::RevealMoves {
+            player1: String::from("player1"),
+            player2: String::from("player2"),
+            game_moves: game_moves.to_vec(),
+            nonce: String::from(nonce),
+        };
+
+        // every hand needs a move
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            reveal_message(&player1_moves[..2], "1"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMoveSequence {}));
+
+        // and the moves have to match the commitment
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            reveal_message(&player1_moves, "2"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            reveal_message(&player1_moves, "1"),
+        )
+        .unwrap();
+        let game_state = game_states()
+            .load(deps.as_ref().storage, (b"player1", b"player2"))
+            .unwrap();
+        assert_eq!(
+            game_state.player1_move,
+            Some(PlayerMove::GameMoves(player1_moves.clone()))
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &[]),
+            reveal_message(&player2_moves, "1"),
+        )
+        .unwrap();
+        assert_eq!(
+            res.events
+                .iter()
+                .filter(|event| event.ty == "rps_hand_resolved")
+                .count(),
+            2
+        );
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player1"),
+                amount: coins(5, "token"),
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player1"),
+                amount: coins(5, "token"),
+            })
+        );
+        assert!(game_states()
+            .may_load(deps.as_ref().storage, (b"player1", b"player2"))
+            .unwrap()
+            .is_none());
+
+        let leaderboard = get_leaderboard(deps.as_ref(), None, None)
+            .unwrap()
+            .leaderboard;
+        assert_eq!(leaderboard[0].address, Addr::unchecked("player1"));
+        assert_eq!(leaderboard[0].num_games_won, 1);
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_channel() {
+        use k256::ecdsa::signature::Signer;
+        use k256::ecdsa::{Signature, SigningKey};
+
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let channel_message = ExecuteMsg::JoinGame {
+            num_hands_to_win: 2,
+            tie_break: None,
+            mode: Some(GameMode::Channel),
+            referrer: None,
+            reliable_only: None,
+        };
+
+        // channel players need a public key first
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(5, "token")),
+            channel_message.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::MissingPubkey {}));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            ExecuteMsg::SetPubkey {
+                pubkey: Binary::from(vec![2; 10]),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPubkey {}));
+
+        let player1_key = SigningKey::from_bytes(&[1; 32]).unwrap();
+        let player2_key = SigningKey::from_bytes(&[2; 32]).unwrap();
+        for (players, key) in [
+            (["player1", "player3"], &player1_key),
+            (["player2", "player4"], &player2_key),
+        ] {
+            for player in players {
+                execute(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info(player, &[]),
+                    ExecuteMsg::SetPubkey {
+                        pubkey: Binary::from(key.verifying_key().to_bytes().to_vec()),
+                    },
+                )
+                .unwrap();
+            }
+        }
+        assert!(get_pubkey(deps.as_ref(), String::from("player1"))
+            .unwrap()
+            .pubkey
+            .is_some());
+
+        for player in ["player1", "player2", "player3", "player4"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(5, "token")),
+                channel_message.clone(),
+            )
+            .unwrap();
+        }
+
+        let sign = |key: &SigningKey, state: &ChannelState| {
+            let sign_doc = ChannelSignDoc {
+                contract: mock_env().contract.address.to_string(),
+                state: state.clone(),
+            };
+            let signature: Signature = key.sign(&to_vec(&sign_doc).unwrap());
+            Binary::from(signature.as_ref().to_vec())
+        };
+        let signed = |state: ChannelState| SignedChannelState {
+            player1_signature: sign(&player1_key, &state),
+            player2_signature: sign(&player2_key, &state),
+            state,
+        };
+        let channel_state = |game_id, nonce, player1_hands_won, player2_hands_won| ChannelState {
+            game_id,
+            nonce,
+            player1_hands_won,
+            player2_hands_won,
+            hands_tied: 0,
+        };
+
+        // player3 and player4 play their game off-chain and settle it in one go
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player3", &[]),
+            ExecuteMsg::SettleChannel {
+                player1: String::from("player3"),
+                player2: String::from("player4"),
+                signed_state: SignedChannelState {
+                    player2_signature: sign(&player1_key, &channel_state(2, 3, 2, 1)),
+                    ..signed(channel_state(2, 3, 2, 1))
+                },
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature {}));
+
+        // the state has to belong to this game
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player3", &[]),
+            ExecuteMsg::SettleChannel {
+                player1: String::from("player3"),
+                player2: String::from("player4"),
+                signed_state: signed(channel_state(1, 3, 2, 1)),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidChannelState {}));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player4", &[]),
+            ExecuteMsg::SettleChannel {
+                player1: String::from("player3"),
+                player2: String::from("player4"),
+                signed_state: signed(channel_state(2, 3, 2, 1)),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player3"),
+                amount: coins(5, "token"),
+            })
+        );
+        assert!(game_states()
+            .may_load(deps.as_ref().storage, (b"player3", b"player4"))
+            .unwrap()
+            .is_none());
+
+        // a newer state settles a disputed game within the challenge window
+        for player in ["player3", "player4"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(5, "token")),
+                channel_message.clone(),
+            )
+            .unwrap();
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player3", &[]),
+            ExecuteMsg::DisputeChannel {
+                player1: String::from("player3"),
+                player2: String::from("player4"),
+                signed_state: None,
+            },
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player4", &[]),
+            ExecuteMsg::SettleChannel {
+                player1: String::from("player3"),
+                player2: String::from("player4"),
+                signed_state: signed(channel_state(3, 1, 0, 2)),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player4"),
+                amount: coins(5, "token"),
+            })
+        );
+
+        // player1 and player2 can't play on-chain until someone disputes
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            ExecuteMsg::CommitMove {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                hashed_move: String::from("hash"),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::WrongGameMode {}));
+
+        // keys can't be swapped out from under a channel game
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &[]),
+            ExecuteMsg::SetPubkey {
+                pubkey: Binary::from(player1_key.verifying_key().to_bytes().to_vec()),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PubkeyInUse {}));
+
+        // states from before the last one applied can't be replayed
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &[]),
+            ExecuteMsg::DisputeChannel {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                signed_state: Some(signed(channel_state(1, 0, 0, 1))),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidChannelState {}));
+
+        // player2 stops signing after losing the first hand off-chain
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            ExecuteMsg::DisputeChannel {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                signed_state: Some(signed(channel_state(1, 1, 1, 0))),
+            },
+        )
+        .unwrap();
+        let game_state = game_states()
+            .load(deps.as_ref().storage, (b"player1", b"player2"))
+            .unwrap();
+        assert!(game_state.channel_disputed);
+        assert_eq!(game_state.player1_hands_won, 1);
+
+        // within the challenge window player2 brings in the newer state where they caught up,
+        // which doesn't push the claim timeout back
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(60);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("player2", &[]),
+            ExecuteMsg::DisputeChannel {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                signed_state: Some(signed(channel_state(1, 2, 1, 1))),
+            },
+        )
+        .unwrap();
+        let game_state = game_states()
+            .load(deps.as_ref().storage, (b"player1", b"player2"))
+            .unwrap();
+        assert_eq!(game_state.player2_hands_won, 1);
+        assert_eq!(game_state.channel_nonce, 2);
+        assert_eq!(game_state.updated_at, mock_env().block.time.nanos());
+        assert_eq!(
+            game_state.channel_disputed_at,
+            mock_env().block.time.nanos()
+        );
+
+        // the dispute can't be restarted without a newer state, and the older state is gone
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("player1", &[]),
+            ExecuteMsg::DisputeChannel {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                signed_state: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidChannelState {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("player1", &[]),
+            ExecuteMsg::DisputeChannel {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                signed_state: Some(signed(channel_state(1, 1, 1, 0))),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidChannelState {}));
+
+        // nor is the game played on-chain yet
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("player1", &[]),
+            ExecuteMsg::CommitMove {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                hashed_move: String::from("hash"),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::WrongGameMode {}));
+
+        // once the window closes signed states aren't accepted any more, not even newer ones
+        let mut env = mock_env();
+        env.block.time = env
+            .block
+            .time
+            .plus_nanos(CHANNEL_CHALLENGE_WINDOW)
+            .plus_seconds(1);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("player2", &[]),
+            ExecuteMsg::SettleChannel {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                signed_state: signed(channel_state(1, 3, 1, 2)),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ChannelDisputed {}));
+
+        // and keys can be rotated again
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("player2", &[]),
+            ExecuteMsg::SetPubkey {
+                pubkey: Binary::from(player2_key.verifying_key().to_bytes().to_vec()),
+            },
+        )
+        .unwrap();
+
+        // the rest of the game is played with commits and reveals
+        let res = play_moves_at(
+            &mut deps,
+            env,
+            "player1",
+            "player2",
+            GameMove::Paper,
+            GameMove::Rock,
+        );
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attribute| attribute.key == "game_won" && attribute.value == "player1"));
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_relay() {
+        use k256::ecdsa::signature::Signer;
+        use k256::ecdsa::{Signature, SigningKey};
+
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let player1_key = SigningKey::from_bytes(&[1; 32]).unwrap();
+        let other_key = SigningKey::from_bytes(&[2; 32]).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            ExecuteMsg::SetPubkey {
+                pubkey: Binary::from(player1_key.verifying_key().to_bytes().to_vec()),
+            },
+        )
+        .unwrap();
+
+        let join_game_message = ExecuteMsg::JoinGame {
+            num_hands_to_win: 1,
+            tie_break: None,
+            mode: None,
+            referrer: None,
+            reliable_only: None,
+        };
+        for player in ["player1", "player2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(10, "token")),
+                join_game_message.clone(),
+            )
+            .unwrap();
+        }
+
+        let relay_message =
+            |key: &SigningKey, relayed_move: RelayedMove, relay_nonce: u64, fee: u128| {
+                let sign_doc = RelaySignDoc {
+                    contract: mock_env().contract.address.to_string(),
+                    game_id: 1,
+                    hand: 1,
+                    relayed_move: relayed_move.clone(),
+                    relay_nonce,
+                    fee: coins(fee, "token"),
+                };
+                let signature: Signature = key.sign(&to_vec(&sign_doc).unwrap());
+                ExecuteMsg::RelayMove(RelayMoveMsg {
+                    player1: String::from("player1"),
+                    player2: String::from("player2"),
+                    player: String::from("player1"),
+                    relayed_move,
+                    relay_nonce,
+                    fee: coins(fee, "token"),
+                    signature: Binary::from(signature.as_ref().to_vec()),
+                })
+            };
+        let commit = RelayedMove::CommitMove {
+            hashed_move: format!(
+                "{:x}",
+                Sha256::digest(format!("{}{}", GameMove::Paper, "1").as_bytes())
+            ),
+        };
+
+        // only the player's key can sign their moves
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("relayer", &[]),
+            relay_message(&other_key, commit.clone(), 0, 1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature {}));
+
+        // the fee has to leave some of the bet
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("relayer", &[]),
+            relay_message(&player1_key, commit.clone(), 0, 10),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRelayerFee {}));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("relayer", &[]),
+            relay_message(&player1_key, commit.clone(), 0, 1),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("relayer"),
+                amount: coins(1, "token"),
+            })
+        );
+        assert_eq!(
+            get_relay_nonce(deps.as_ref(), String::from("player1"))
+                .unwrap()
+                .relay_nonce,
+            1
+        );
+
+        // a signed move can't be submitted twice
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("relayer", &[]),
+            relay_message(&player1_key, commit, 0, 1),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRelayNonce {}));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &[]),
+            ExecuteMsg::CommitMove {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                hashed_move: format!(
+                    "{:x}",
+                    Sha256::digest(format!("{}{}", GameMove::Rock, "1").as_bytes())
+                ),
+            },
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("relayer", &[]),
+            relay_message(
+                &player1_key,
+                RelayedMove::RevealMove {
+                    game_move: GameMove::Paper,
+                    nonce: String::from("1"),
+                },
+                1,
+                1,
+            ),
+        )
+        .unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &[]),
+            ExecuteMsg::RevealMove {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                game_move: GameMove::Rock,
+                nonce: String::from("1"),
+            },
+        )
+        .unwrap();
+
+        // player1 wins both bets, less the fees they paid the relayer
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player1"),
+                amount: coins(8, "token"),
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player1"),
+                amount: coins(10, "token"),
+            })
+        );
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_session_keys() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let start_game = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+            for player in ["player1", "player2"] {
+                execute(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info(player, &coins(5, "token")),
+                    ExecuteMsg::JoinGame {
+                        num_hands_to_win: 1,
+                        tie_break: None,
+                        mode: None,
+                        referrer: None,
+                        reliable_only: None,
+                    },
+                )
+                .unwrap();
+            }
+        };
+        let commit_message = |game_move: GameMove| ExecuteMsg::CommitMove {
+            player1: String::from("player1"),
+            player2: String::from("player2"),
+            hashed_move: format!(
+                "{:x}",
+                Sha256::digest(format!("{}{}", game_move, "1").as_bytes())
+            ),
+        };
+        let reveal_message = |game_move: GameMove| ExecuteMsg::RevealMove {
+            player1: String::from("player1"),
+            player2: String::from("player2"),
+            game_move,
+            nonce: String::from("1"),
+        };
+        let grant_message = |expires_in: u64, max_games: u32| ExecuteMsg::GrantSessionKey {
+            delegate: String::from("hotkey"),
+            expires_at: mock_env().block.time.plus_seconds(expires_in).nanos(),
+            max_games,
+        };
+
+        // the key has to be usable for at least one game
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            grant_message(3600, 0),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSessionKey {}));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            grant_message(3600, 1),
+        )
+        .unwrap();
+        assert_eq!(
+            get_session_keys(deps.as_ref(), String::from("player1"))
+                .unwrap()
+                .session_keys
+                .len(),
+            1
+        );
+
+        start_game(&mut deps);
+
+        // only a delegate of one of the players can play
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("stranger", &[]),
+            commit_message(GameMove::Paper),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // the hot key plays player1's moves
+        for (sender, message) in [
+            ("hotkey", commit_message(GameMove::Paper)),
+            ("player2", commit_message(GameMove::Rock)),
+            ("hotkey", reveal_message(GameMove::Paper)),
+        ] {
+            execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), message).unwrap();
+        }
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &[]),
+            reveal_message(GameMove::Rock),
+        )
+        .unwrap();
+
+        // but the winnings still go to player1
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player1"),
+                amount: coins(5, "token"),
+            })
+        );
+        let session_key = get_session_key(
+            deps.as_ref(),
+            String::from("player1"),
+            String::from("hotkey"),
+        )
+        .unwrap()
+        .session_key
+        .unwrap();
+        assert_eq!(session_key.game_ids, vec![1]);
+
+        // the key only had one game
+        start_game(&mut deps);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("hotkey", &[]),
+            commit_message(GameMove::Paper),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SessionKeyExhausted {}));
+
+        // granting it again starts it over, and it can forfeit for player1
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            grant_message(3600, 2),
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("hotkey", &[]),
+            ExecuteMsg::ForfeitGame {
+                player: Some(String::from("player1")),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player2"),
+                amount: coins(5, "token"),
+            })
+        );
+
+        // it can't forfeit for the opponent
+        start_game(&mut deps);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("hotkey", &[]),
+            ExecuteMsg::ForfeitGame {
+                player: Some(String::from("player2")),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // nor play once it has expired
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(7200);
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("hotkey", &[]),
+            commit_message(GameMove::Paper),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SessionKeyExpired {}));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            ExecuteMsg::RevokeSessionKey {
+                delegate: String::from("hotkey"),
+            },
+        )
+        .unwrap();
+        assert!(get_session_key(
+            deps.as_ref(),
+            String::from("player1"),
+            String::from("hotkey")
+        )
+        .unwrap()
+        .session_key
+        .is_none());
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("hotkey", &[]),
+            commit_message(GameMove::Paper),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // a delegate of both players can't play, it's unclear who for
+        for player in ["player1", "player2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &[]),
+                grant_message(3600, 1),
+            )
+            .unwrap();
+        }
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("hotkey", &[]),
+            commit_message(GameMove::Paper),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SessionKeyAmbiguous {}));
+
+        // but once player1's key expires it plays for player2
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            grant_message(60, 1),
+        )
+        .unwrap();
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(120);
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("hotkey", &[]),
+            commit_message(GameMove::Paper),
+        )
+        .unwrap();
+        let game_state = game_states()
+            .load(deps.as_ref().storage, (b"player1", b"player2"))
+            .unwrap();
+        assert!(game_state.player1_move.is_none());
+        assert!(game_state.player2_move.is_some());
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_house() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("admin")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let hash = |game_move: GameMove, nonce: &str| {
+            format!(
+                "{:x}",
+                Sha256::digest(format!("{}{}", game_move, nonce).as_bytes())
+            )
+        };
+        let play_message = |game_move: GameMove| ExecuteMsg::PlayHouse {
+            hashed_move: hash(game_move, "1"),
+        };
+        // links[i] is the link the i-th dealt house move opens, links[i + 1] the one it reveals
+        let links = {
+            let mut links = vec![String::from("anchor")];
+            for (game_move, nonce) in [
+                (GameMove::Scissors, "h1"),
+                (GameMove::Rock, "h2"),
+                (GameMove::Paper, "h3"),
+            ]
+            .iter()
+            .rev()
+            {
+                let previous_link = links.last().unwrap().clone();
+                links.push(hash(
+                    game_move.clone(),
+                    &format!("{}{}", nonce, previous_link),
+                ));
+            }
+            links.reverse();
+            links
+        };
+        let reveal_message =
+            |game_move: GameMove, nonce: &str, position: usize| ExecuteMsg::RevealHouseMove {
+                game_move,
+                nonce: String::from(nonce),
+                previous_link: links[position + 1].clone(),
+            };
+
+        // nobody to play against yet
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(5, "token")),
+            play_message(GameMove::Rock),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::HouseUnavailable {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("house", &[]),
+            ExecuteMsg::CommitHouseChain {
+                head: links[0].clone(),
+                length: 3,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::HouseUnavailable {}));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            ExecuteMsg::SetHouse {
+                operator: String::from("house"),
+                max_bet: coins(10, "token"),
+                max_exposure: coins(15, "token"),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Admin(_)));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetHouse {
+                operator: String::from("house"),
+                max_bet: coins(10, "token"),
+                max_exposure: coins(15, "token"),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &coins(100, "token")),
+            ExecuteMsg::DepositBankroll {},
+        )
+        .unwrap();
+
+        // only the operator commits the house chain
+        let commit_message = ExecuteMsg::CommitHouseChain {
+            head: links[0].clone(),
+            length: 3,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            commit_message.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("house", &[]),
+            commit_message.clone(),
+        )
+        .unwrap();
+        assert_eq!(get_house(deps.as_ref()).unwrap().moves_available, 3);
+
+        // nothing has been dealt to reveal
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("house", &[]),
+            reveal_message(GameMove::Scissors, "h1", 0),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidGame {}));
+
+        // over the max bet
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(11, "token")),
+            play_message(GameMove::Rock),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::HouseLimitExceeded {}));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(10, "token")),
+            play_message(GameMove::Rock),
+        )
+        .unwrap();
+        let house = get_house(deps.as_ref()).unwrap();
+        assert_eq!(house.bankroll, coins(90, "token"));
+        assert_eq!(house.exposure, coins(10, "token"));
+        assert_eq!(house.moves_available, 2);
+
+        // the chain can't be swapped or handed to another operator while a game waits on it
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("house", &[]),
+            commit_message,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::HouseGamesOpen {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetHouse {
+                operator: String::from("other_house"),
+                max_bet: coins(10, "token"),
+                max_exposure: coins(15, "token"),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::HouseGamesOpen {}));
+
+        // over the exposure limit while player1's game is going
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &coins(10, "token")),
+            play_message(GameMove::Rock),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::HouseLimitExceeded {}));
+
+        // the house can't reveal before the player
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("house", &[]),
+            reveal_message(GameMove::Scissors, "h1", 0),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            ExecuteMsg::RevealHouseGame {
+                game_move: GameMove::Rock,
+                nonce: String::from("1"),
+            },
+        )
+        .unwrap();
+
+        // only the operator opens links
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            reveal_message(GameMove::Scissors, "h1", 0),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // the house is held to the move it committed and to the link after it
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("house", &[]),
+            ExecuteMsg::RevealHouseMove {
+                game_move: GameMove::Scissors,
+                nonce: String::from("h1"),
+                previous_link: links[2].clone(),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("house", &[]),
+            reveal_message(GameMove::Paper, "h1", 0),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // rock beats scissors, the player gets both bets
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("house", &[]),
+            reveal_message(GameMove::Scissors, "h1", 0),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player1"),
+                amount: coins(20, "token"),
+            })
+        );
+        let house = get_house(deps.as_ref()).unwrap();
+        assert_eq!(house.bankroll, coins(90, "token"));
+        assert!(house.exposure.is_empty());
+        assert!(get_house_game(deps.as_ref(), String::from("player1"))
+            .unwrap()
+            .game
+            .is_none());
+
+        // player2 never reveals, so the house takes the bet
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &coins(5, "token")),
+            play_message(GameMove::Paper),
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ClaimHouseGame {
+                player: String::from("player2"),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(61);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::ClaimHouseGame {
+                player: String::from("player2"),
+            },
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(
+            get_house(deps.as_ref()).unwrap().bankroll,
+            coins(95, "token")
+        );
+
+        // the claimed game's link is still opened to move the chain along
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("house", &[]),
+            reveal_message(GameMove::Rock, "h2", 1),
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(
+            get_house(deps.as_ref()).unwrap().chain.unwrap().head,
+            links[2]
+        );
+
+        // the house doesn't reveal in time, so it forfeits to player1
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("player1", &coins(5, "token")),
+            play_message(GameMove::Paper),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("player1", &[]),
+            ExecuteMsg::RevealHouseGame {
+                game_move: GameMove::Paper,
+                nonce: String::from("1"),
+            },
+        )
+        .unwrap();
+        env.block.time = env.block.time.plus_seconds(61);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("player1", &[]),
+            ExecuteMsg::ClaimHouseGame {
+                player: String::from("player1"),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player1"),
+                amount: coins(10, "token"),
+            })
+        );
+
+        // every committed house move is used up
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("player1", &coins(5, "token")),
+            play_message(GameMove::Rock),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::HouseUnavailable {}));
+
+        // with no game waiting, a new operator takes over and starts without a chain
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("admin", &[]),
+            ExecuteMsg::SetHouse {
+                operator: String::from("other_house"),
+                max_bet: coins(10, "token"),
+                max_exposure: coins(15, "token"),
+            },
+        )
+        .unwrap();
+        let house = get_house(deps.as_ref()).unwrap();
+        assert!(house.chain.is_none());
+        assert_eq!(house.moves_available, 0);
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_treasury() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("admin")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // Moves the funds a call sent and paid out through the contract's mock balance
+        fn settle_balance(
+            deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+            funds: &[Coin],
+            res: &Response,
+        ) {
+            let mut balance = deps
+                .as_ref()
+                .querier
+                .query_all_balances(MOCK_CONTRACT_ADDR)
+                .unwrap();
+            add_coins(&mut balance, funds);
+            for sub_msg in &res.messages {
+                if let CosmosMsg::Bank(BankMsg::Send { amount, .. }) = &sub_msg.msg {
+                    balance = sub_coins(&balance, amount).unwrap();
+                }
+            }
+            balance.retain(|coin| !coin.amount.is_zero());
+            deps.querier.update_balance(MOCK_CONTRACT_ADDR, balance);
+
+            let treasury = get_treasury(deps.as_ref(), mock_env()).unwrap();
+            assert!(treasury.solvent, "{:?}", treasury);
+            assert_treasury_totals(deps);
+        }
+        let run = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+                   sender: &str,
+                   funds: &[Coin],
+                   msg: ExecuteMsg|
+         -> Result<Response, ContractError> {
+            let res = execute(deps.as_mut(), mock_env(), mock_info(sender, funds), msg);
+            match &res {
+                Ok(ok) => settle_balance(deps, funds, ok),
+                Err(_) => settle_balance(deps, &[], &Response::new()),
+            }
+            res
+        };
+        let join_message = || ExecuteMsg::JoinGame {
+            num_hands_to_win: 1,
+            tie_break: None,
+            mode: None,
+            referrer: None,
+            reliable_only: None,
+        };
+
+        run(
+            &mut deps,
+            "admin",
+            &coins(100, "token"),
+            ExecuteMsg::DepositBankroll {},
+        )
+        .unwrap();
+        run(
+            &mut deps,
+            "anyone",
+            &coins(30, "token"),
+            ExecuteMsg::FundPrizePool {},
+        )
+        .unwrap();
+
+        // queue, leave and come back
+        run(&mut deps, "player1", &coins(5, "token"), join_message()).unwrap();
+        let treasury = get_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(treasury.queue_deposits, coins(5, "token"));
+        run(&mut deps, "player1", &[], ExecuteMsg::LeaveWaitingQueue {}).unwrap();
+        run(&mut deps, "player1", &coins(5, "token"), join_message()).unwrap();
+        run(&mut deps, "player2", &coins(5, "token"), join_message()).unwrap();
+        let treasury = get_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(treasury.game_escrow, coins(10, "token"));
+        assert_eq!(treasury.liabilities, coins(140, "token"));
+        assert_eq!(treasury.balance, coins(140, "token"));
+
+        // the winner is paid and both players can put up a rematch
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Paper,
+        );
+        settle_balance(&mut deps, &[], &res);
+        run(
+            &mut deps,
+            "player1",
+            &coins(5, "token"),
+            ExecuteMsg::OfferRematch {
+                opponent: String::from("player2"),
+            },
+        )
+        .unwrap();
+        let treasury = get_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(treasury.queue_deposits, coins(5, "token"));
+
+        // a house game locks the house's matching stake
+        run(
+            &mut deps,
+            "admin",
+            &[],
+            ExecuteMsg::SetHouse {
+                operator: String::from("house"),
+                max_bet: coins(10, "token"),
+                max_exposure: coins(50, "token"),
+            },
+        )
+        .unwrap();
+        run(
+            &mut deps,
+            "house",
+            &[],
+            ExecuteMsg::CommitHouseChain {
+                head: format!(
+                    "{:x}",
+                    Sha256::digest(format!("{}{}{}", GameMove::Rock, "h", "anchor").as_bytes())
+                ),
+                length: 1,
+            },
+        )
+        .unwrap();
+        run(
+            &mut deps,
+            "player3",
+            &coins(10, "token"),
+            ExecuteMsg::PlayHouse {
+                hashed_move: format!(
+                    "{:x}",
+                    Sha256::digest(format!("{}{}", GameMove::Paper, "1").as_bytes())
+                ),
+            },
+        )
+        .unwrap();
+        let treasury = get_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(treasury.house_bankroll, coins(90, "token"));
+        assert_eq!(treasury.house_exposure, coins(10, "token"));
+
+        // only the bankroll can be withdrawn, not the locked stake or player funds
+        let err = run(
+            &mut deps,
+            "admin",
+            &[],
+            ExecuteMsg::WithdrawBankroll {
+                amount: coins(91, "token"),
+                to: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientBankroll {}));
+        let err = run(
+            &mut deps,
+            "player3",
+            &[],
+            ExecuteMsg::WithdrawBankroll {
+                amount: coins(1, "token"),
+                to: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Admin(_)));
+        run(
+            &mut deps,
+            "admin",
+            &[],
+            ExecuteMsg::WithdrawBankroll {
+                amount: coins(40, "token"),
+                to: Some(String::from("treasurer")),
+            },
+        )
+        .unwrap();
+
+        run(
+            &mut deps,
+            "player3",
+            &[],
+            ExecuteMsg::RevealHouseGame {
+                game_move: GameMove::Paper,
+                nonce: String::from("1"),
+            },
+        )
+        .unwrap();
+        run(
+            &mut deps,
+            "house",
+            &[],
+            ExecuteMsg::RevealHouseMove {
+                game_move: GameMove::Rock,
+                nonce: String::from("h"),
+                previous_link: String::from("anchor"),
+            },
+        )
+        .unwrap();
+        let treasury = get_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(treasury.house_bankroll, coins(50, "token"));
+        assert!(treasury.house_exposure.is_empty());
+        assert_eq!(treasury.liabilities, coins(85, "token"));
+        assert_eq!(treasury.balance, coins(85, "token"));
+
+        // funds that went missing elsewhere can't be covered by the bankroll
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(80, "token"));
+        assert!(!get_treasury(deps.as_ref(), mock_env()).unwrap().solvent);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::WithdrawBankroll {
+                amount: coins(10, "token"),
+                to: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientBankroll {}));
+
+        // a contract from before the running totals starts them off from the records on migration
+        QUEUE_DEPOSITS.remove(deps.as_mut().storage);
+        let treasury = get_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert!(treasury.queue_deposits.is_empty());
+        assert_eq!(treasury.liabilities, coins(80, "token"));
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let treasury = get_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(treasury.queue_deposits, coins(5, "token"));
+        assert_eq!(treasury.liabilities, coins(85, "token"));
+    }
+
+    #[test]
+    fn test_fees() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("admin")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // a distribution share needs somewhere to go
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetFeeConfig {
+                fee_percentage: 10,
+                distribution_contract: None,
+                distribution_percentage: 30,
+                referral_percentage: 0,
+                prize_pool_percentage: 0,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFeeConfig {}));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetFeeConfig {
+                fee_percentage: 10,
+                distribution_contract: Some(String::from("stakers")),
+                distribution_percentage: 30,
+                referral_percentage: 0,
+                prize_pool_percentage: 0,
+            },
+        )
+        .unwrap();
+
+        let start_game = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+            for player in ["player1", "player2"] {
+                execute(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info(player, &coins(50, "token")),
+                    ExecuteMsg::JoinGame {
+                        num_hands_to_win: 1,
+                        tie_break: None,
+                        mode: None,
+                        referrer: None,
+                        reliable_only: None,
+                    },
+                )
+                .unwrap();
+            }
+        };
+
+        // the fee stays in the contract instead of going out with the winnings
+        start_game(&mut deps);
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Scissors,
+        );
+        let paid: Vec<_> = res
+            .messages
+            .iter()
+            .map(|sub_msg| sub_msg.msg.clone())
+            .collect();
+        assert_eq!(
+            paid,
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("player1"),
+                    amount: coins(45, "token"),
+                });
+                2
+            ]
+        );
+        let fees = get_fees(deps.as_ref()).unwrap();
+        assert_eq!(fees.accrued_fees, coins(10, "token"));
+        assert_eq!(fees.lifetime_fees, coins(10, "token"));
+
+        // ties are refunded in full
+        start_game(&mut deps);
+        play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Paper,
+            GameMove::Paper,
+        );
+        assert_eq!(
+            get_fees(deps.as_ref()).unwrap().lifetime_fees,
+            coins(10, "token")
+        );
+
+        let withdraw_message = |amount: u128| ExecuteMsg::WithdrawFees {
+            to: String::from("treasury"),
+            denom: String::from("token"),
+            amount: Uint128::from(amount),
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            withdraw_message(10),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Admin(_)));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            withdraw_message(11),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFees {}));
+
+        // the distribution contract gets its share of the withdrawal
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            withdraw_message(10),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("stakers"),
+                amount: coins(3, "token"),
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("treasury"),
+                amount: coins(7, "token"),
+            })
+        );
+        let fees = get_fees(deps.as_ref()).unwrap();
+        assert!(fees.accrued_fees.is_empty());
+        assert_eq!(fees.lifetime_fees, coins(10, "token"));
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_referrals() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("admin")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetFeeConfig {
+                fee_percentage: 10,
+                distribution_contract: None,
+                distribution_percentage: 0,
+                referral_percentage: 50,
+                prize_pool_percentage: 0,
+            },
+        )
+        .unwrap();
+
+        let join = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+                    player: &str,
+                    referrer: Option<&str>| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(100, "token")),
+                ExecuteMsg::JoinGame {
+                    num_hands_to_win: 1,
+                    tie_break: None,
+                    mode: None,
+                    referrer: referrer.map(String::from),
+                    reliable_only: None,
+                },
+            )
+            .unwrap()
+        };
+
+        // players can't refer themselves
+        join(&mut deps, "player1", Some("player1"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            ExecuteMsg::LeaveWaitingQueue {},
+        )
+        .unwrap();
+        assert_eq!(
+            get_referral_stats(deps.as_ref(), String::from("player1"))
+                .unwrap()
+                .referral_count,
+            0
+        );
+
+        let res = join(&mut deps, "player1", Some("promoter"));
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "referrer" && attr.value == "promoter"));
+        join(&mut deps, "player2", Some("promoter"));
+
+        // the referrer gets half the fee on both stakes, whoever wins
+        play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Scissors,
+        );
+        let stats = get_referral_stats(deps.as_ref(), String::from("promoter")).unwrap();
+        assert_eq!(stats.referral_count, 2);
+        assert_eq!(stats.earned, coins(10, "token"));
+        assert_eq!(stats.balance, coins(10, "token"));
+        let fees = get_fees(deps.as_ref()).unwrap();
+        assert_eq!(fees.accrued_fees, coins(10, "token"));
+        assert_eq!(fees.lifetime_fees, coins(20, "token"));
+
+        // a player is only referred once
+        let res = join(&mut deps, "player1", Some("someone_else"));
+        assert!(!res.attributes.iter().any(|attr| attr.key == "referrer"));
+        assert_eq!(
+            get_referral_stats(deps.as_ref(), String::from("someone_else"))
+                .unwrap()
+                .referral_count,
+            0
+        );
+
+        // players who have played before can't be referred
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            ExecuteMsg::LeaveWaitingQueue {},
+        )
+        .unwrap();
+        join(&mut deps, "player3", Some("someone_else"));
+        join(&mut deps, "player2", Some("someone_else"));
+        play_moves(
+            &mut deps,
+            "player3",
+            "player2",
+            GameMove::Rock,
+            GameMove::Scissors,
+        );
+        let stats = get_referral_stats(deps.as_ref(), String::from("promoter")).unwrap();
+        assert_eq!(stats.earned, coins(15, "token"));
+        let stats = get_referral_stats(deps.as_ref(), String::from("someone_else")).unwrap();
+        assert_eq!(stats.referral_count, 1);
+        assert_eq!(stats.earned, coins(5, "token"));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("promoter", &[]),
+            ExecuteMsg::ClaimReferralRewards {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("promoter"),
+                amount: coins(15, "token"),
+            })
+        );
+        let stats = get_referral_stats(deps.as_ref(), String::from("promoter")).unwrap();
+        assert!(stats.balance.is_empty());
+        assert_eq!(stats.earned, coins(15, "token"));
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("promoter", &[]),
+            ExecuteMsg::ClaimReferralRewards {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoReferralRewards {}));
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_side_bets() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("admin")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        for player in ["player1", "player2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(5, "token")),
+                ExecuteMsg::JoinGame {
+                    num_hands_to_win: 1,
+                    tie_break: None,
+                    mode: None,
+                    referrer: None,
+                    reliable_only: None,
+                },
+            )
+            .unwrap();
+        }
+
+        let side_bet = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+                        bettor: &str,
+                        funds: &[Coin],
+                        on: BetSide| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(bettor, funds),
+                ExecuteMsg::PlaceSideBet {
+                    player1: String::from("player1"),
+                    player2: String::from("player2"),
+                    on,
+                },
+            )
+        };
+        let was_paid = |res: &Response, to: &str, amount: u128| {
+            res.messages.iter().any(|sub_msg| {
+                sub_msg.msg
+                    == CosmosMsg::Bank(BankMsg::Send {
+                        to_address: String::from(to),
+                        amount: coins(amount, "token"),
+                    })
+            })
+        };
+
+        // side bets are off until the admin sets a cap
+        let err = side_bet(&mut deps, "alice", &coins(30, "token"), BetSide::Player1).unwrap_err();
+        assert!(matches!(err, ContractError::SideBetsClosed {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetSideBetCap {
+                cap: coins(100, "token"),
+            },
+        )
+        .unwrap();
+
+        // players can't bet on their own game
+        let err =
+            side_bet(&mut deps, "player1", &coins(30, "token"), BetSide::Player1).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let err = side_bet(
+            &mut deps,
+            "alice",
+            &[coin(30, "token"), coin(30, "other")],
+            BetSide::Player1,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::IncorrectFunds {}));
+
+        side_bet(&mut deps, "alice", &coins(30, "token"), BetSide::Player1).unwrap();
+        side_bet(&mut deps, "bob", &coins(10, "token"), BetSide::Player1).unwrap();
+        side_bet(&mut deps, "carol", &coins(20, "token"), BetSide::Player2).unwrap();
+
+        // one bet per spectator, within the cap
+        let err = side_bet(&mut deps, "alice", &coins(5, "token"), BetSide::Player2).unwrap_err();
+        assert!(matches!(err, ContractError::SideBetsClosed {}));
+        let err = side_bet(&mut deps, "dave", &coins(41, "token"), BetSide::Player2).unwrap_err();
+        assert!(matches!(err, ContractError::SideBetCapExceeded {}));
+
+        let side_bets = get_side_bets(
+            deps.as_ref(),
+            String::from("player1"),
+            String::from("player2"),
+        )
+        .unwrap();
+        assert_eq!(side_bets.pool.player1, coins(40, "token"));
+        assert_eq!(side_bets.pool.player2, coins(20, "token"));
+        assert_eq!(side_bets.bets.len(), 3);
+        assert_eq!(
+            get_treasury(deps.as_ref(), mock_env()).unwrap().side_bets,
+            coins(60, "token")
+        );
+
+        // player1's backers split carol's bet by how much they put in
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Scissors,
+        );
+        assert!(was_paid(&res, "alice", 45));
+        assert!(was_paid(&res, "bob", 15));
+        assert!(!res.messages.iter().any(|sub_msg| matches!(
+            &sub_msg.msg,
+            CosmosMsg::Bank(BankMsg::Send { to_address, .. }) if to_address == "carol"
+        )));
+        assert!(get_treasury(deps.as_ref(), mock_env())
+            .unwrap()
+            .side_bets
+            .is_empty());
+
+        // the rematch takes no more bets once a move is revealed
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(5, "token")),
+            ExecuteMsg::OfferRematch {
+                opponent: String::from("player2"),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &coins(5, "token")),
+            ExecuteMsg::AcceptRematch {
+                opponent: String::from("player1"),
+            },
+        )
+        .unwrap();
+        side_bet(&mut deps, "frank", &coins(10, "token"), BetSide::Player2).unwrap();
+        for (player, game_move) in [("player1", GameMove::Paper), ("player2", GameMove::Rock)] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &[]),
+                ExecuteMsg::CommitMove {
+                    player1: String::from("player1"),
+                    player2: String::from("player2"),
+                    hashed_move: format!(
+                        "{:x}",
+                        Sha256::digest(format!("{}{}", game_move, "1").as_bytes())
+                    ),
+                },
+            )
+            .unwrap();
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            ExecuteMsg::RevealMove {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                game_move: GameMove::Paper,
+                nonce: String::from("1"),
+            },
+        )
+        .unwrap();
+        let err = side_bet(&mut deps, "gina", &coins(10, "token"), BetSide::Player1).unwrap_err();
+        assert!(matches!(err, ContractError::SideBetsClosed {}));
+
+        // nobody backed the winner, so frank's bet is refunded
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &[]),
+            ExecuteMsg::RevealMove {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                game_move: GameMove::Rock,
+                nonce: String::from("1"),
+            },
+        )
+        .unwrap();
+        assert!(was_paid(&res, "frank", 10));
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_reliability() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let join = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+                    player: &str,
+                    reliable_only: bool| {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(5, "token")),
+                ExecuteMsg::JoinGame {
+                    num_hands_to_win: 1,
+                    tie_break: None,
+                    mode: None,
+                    referrer: None,
+                    reliable_only: Some(reliable_only),
+                },
+            )
+        };
+        let opponent_found = |res: &Response| res.attributes[2].value == "true";
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &coins(20, "token")),
+            ExecuteMsg::PostPenaltyBond {},
+        )
+        .unwrap();
+        join(&mut deps, "player1", false).unwrap();
+        join(&mut deps, "player2", false).unwrap();
+
+        // the bond can't be pulled out of a game in progress
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            ExecuteMsg::WithdrawPenaltyBond {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PlayerBusy {}));
+
+        // player1 walks away and player2 claims, taking player1's bond as well
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &[]),
+            ExecuteMsg::CommitMove {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                hashed_move: format!(
+                    "{:x}",
+                    Sha256::digest(format!("{}{}", GameMove::Rock, "1").as_bytes())
+                ),
+            },
+        )
+        .unwrap();
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(61);
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("player2", &[]),
+            ExecuteMsg::ClaimGame {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+            },
+        )
+        .unwrap();
+        assert!(res.messages.iter().any(|sub_msg| sub_msg.msg
+            == CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player2"),
+                amount: coins(20, "token"),
+            })));
+
+        let reliability = get_reliability(deps.as_ref(), String::from("player1")).unwrap();
+        assert_eq!(reliability.games_played, 1);
+        assert_eq!(reliability.games_abandoned, 1);
+        assert_eq!(reliability.reliability, 0);
+        assert!(reliability.penalty_bond.is_empty());
+        assert_eq!(
+            get_reliability(deps.as_ref(), String::from("player2"))
+                .unwrap()
+                .reliability,
+            100
+        );
+
+        // abandoners can't join the reliable-only queue
+        let err = join(&mut deps, "player1", true).unwrap_err();
+        assert!(matches!(err, ContractError::Unreliable {}));
+
+        // and reliable-only players aren't matched with the regular queue
+        assert!(!opponent_found(&join(&mut deps, "player2", true).unwrap()));
+        assert!(!opponent_found(&join(&mut deps, "player1", false).unwrap()));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player3", &coins(10, "token")),
+            ExecuteMsg::PostPenaltyBond {},
+        )
+        .unwrap();
+        assert!(opponent_found(&join(&mut deps, "player3", true).unwrap()));
+        assert_eq!(
+            get_treasury(deps.as_ref(), mock_env())
+                .unwrap()
+                .penalty_bonds,
+            coins(10, "token")
+        );
+
+        // forfeits count against reliability too, but don't slash the bond
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player3", &[]),
+            ExecuteMsg::ForfeitGame { player: None },
+        )
+        .unwrap();
+        let reliability = get_reliability(deps.as_ref(), String::from("player3")).unwrap();
+        assert_eq!(reliability.games_forfeited, 1);
+        assert_eq!(reliability.reliability, 0);
+        assert_eq!(reliability.penalty_bond, coins(10, "token"));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player3", &[]),
+            ExecuteMsg::WithdrawPenaltyBond {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("player3"),
+                amount: coins(10, "token"),
+            })
+        );
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_abort_game() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let start_game = |deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+            for player in ["player1", "player2"] {
+                execute(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info(player, &coins(5, "token")),
+                    ExecuteMsg::JoinGame {
+                        num_hands_to_win: 1,
+                        tie_break: None,
+                        mode: None,
+                        referrer: None,
+                        reliable_only: None,
+                    },
+                )
+                .unwrap();
+            }
+        };
+        let abort_message = || ExecuteMsg::AbortGame {
+            player1: String::from("player1"),
+            player2: String::from("player2"),
+        };
+        let mut later = mock_env();
+        later.block.time = later.block.time.plus_seconds(61);
+
+        start_game(&mut deps);
+
+        // players get a chance to move first, up to the end of the window
+        for seconds in [0, 1, 59, 60] {
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(seconds);
+            let err = execute(
+                deps.as_mut(),
+                env,
+                mock_info("player1", &[]),
+                abort_message(),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::Unauthorized {}));
+        }
+        let err = execute(
+            deps.as_mut(),
+            later.clone(),
+            mock_info("stranger", &[]),
+            abort_message(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // both bets go back and nobody's record changes
+        let res = execute(
+            deps.as_mut(),
+            later.clone(),
+            mock_info("player2", &[]),
+            abort_message(),
+        )
+        .unwrap();
+        for player in ["player1", "player2"] {
+            assert!(res.messages.iter().any(|sub_msg| sub_msg.msg
+                == CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from(player),
+                    amount: coins(5, "token"),
+                })));
+            assert!(leaderboard()
+                .may_load(&deps.storage, player.as_bytes())
+                .unwrap()
+                .is_none());
+        }
+        assert_eq!(res.events[0].ty, "rps_game_aborted");
+        assert!(get_game_by_player(deps.as_ref(), String::from("player1"))
+            .unwrap()
+            .game
+            .is_none());
+
+        // once someone has committed, the game has to be claimed instead
+        start_game(&mut deps);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player1", &[]),
+            ExecuteMsg::CommitMove {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                hashed_move: format!(
+                    "{:x}",
+                    Sha256::digest(format!("{}{}", GameMove::Rock, "1").as_bytes())
+                ),
+            },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            later,
+            mock_info("player2", &[]),
+            abort_message(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_start_season_closes_expired_season() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("creator")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let end_time = mock_env().block.time.nanos() + 1_000;
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::StartSeason { end_time },
+        )
+        .unwrap();
+
+        // the running season blocks a new one
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::StartSeason {
+                end_time: end_time + 1_000,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::SeasonInProgress {}));
+
+        // once it has expired the next season closes it without an EndSeason
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_nanos(1_000);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("creator", &[]),
+            ExecuteMsg::StartSeason {
+                end_time: env.block.time.nanos() + 1_000,
+            },
+        )
+        .unwrap();
+
+        let res = get_season_leaderboard(deps.as_ref(), 1, None).unwrap();
+        assert!(res.season.ended);
+        let res = get_season_leaderboard(deps.as_ref(), 2, None).unwrap();
+        assert!(!res.season.ended);
+    }
+
+    #[test]
+    fn test_fees_fund_prize_pool() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("admin")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+        let fee_config = |prize_pool_percentage: u8| ExecuteMsg::SetFeeConfig {
+            fee_percentage: 10,
+            distribution_contract: None,
+            distribution_percentage: 0,
+            referral_percentage: 0,
+            prize_pool_percentage,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            fee_config(101),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFeeConfig {}));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            fee_config(40),
+        )
+        .unwrap();
+
+        for player in ["player1", "player2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(50, "token")),
+                ExecuteMsg::JoinGame {
+                    num_hands_to_win: 1,
+                    tie_break: None,
+                    mode: None,
+                    referrer: None,
+                    reliable_only: None,
+                },
+            )
+            .unwrap();
+        }
+        play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Scissors,
+        );
 
-    Ok(GetGamesResponse { games: game_states })
-}
+        // the prize pool's share of the fee never reaches the withdrawable fees
+        assert_eq!(
+            get_prize_pool(deps.as_ref()).unwrap().prize_pool,
+            coins(4, "token")
+        );
+        let fees = get_fees(deps.as_ref()).unwrap();
+        assert_eq!(fees.accrued_fees, coins(6, "token"));
+        assert_eq!(fees.lifetime_fees, coins(10, "token"));
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{
-        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
-    };
-    use cosmwasm_std::{coins, OwnedDeps};
+        assert_treasury_totals(&mut deps);
+    }
 
     #[test]
-    fn test_leaderboard() {
-        // get deps
-        let mut deps = mock_dependencies(&coins(2, "token"));
+    fn test_tournament_prize_pool_overflow() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // a buy in that can't be multiplied by the bracket size is rejected up front
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::CreateTournament {
+                size: 4,
+                buy_in: coins(u128::MAX / 2, "token"),
+                num_hands_to_win: 1,
+                registration_deadline: mock_env().block.time.nanos() + 1_000,
+                payout_curve: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+    }
 
-        // instantiate smart contract
-        let msg = InstantiateMsg { admin: None };
-        let info = mock_info("creator", &coins(2, "token"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    #[test]
+    fn test_league_tied_series() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let create_league = |entry_fee: Vec<Coin>| ExecuteMsg::CreateLeague {
+            participants: vec![String::from("player1"), String::from("player2")],
+            entry_fee,
+            num_hands_to_win: 1,
+            join_deadline: mock_env().block.time.nanos() + 1_000,
+            round_duration: 1_000,
+        };
+
+        // an entry fee that can't be multiplied by the number of participants is rejected
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            create_league(coins(u128::MAX, "token")),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            create_league(coins(10, "token")),
+        )
+        .unwrap();
+        for player in ["player1", "player2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(10, "token")),
+                ExecuteMsg::JoinLeague { league_id: 1 },
+            )
+            .unwrap();
+        }
 
-        fn play_hand(
-            deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
-            player1_name: String,
-            player2_name: String,
-            bet_amount: u128,
-        ) {
-            // define moves
-            let rock_move_hash = format!(
-                "{:x}",
-                Sha256::digest(format!("{}{}", GameMove::Rock, "1").as_bytes())
-            );
-            let paper_move_hash = format!(
-                "{:x}",
-                Sha256::digest(format!("{}{}", GameMove::Paper, "1").as_bytes())
+        // enough tied hands end the fixture as a tied series
+        let mut res = Response::new();
+        for _ in 0..LEAGUE_MAX_TIES {
+            res = play_moves(
+                &mut deps,
+                "player1",
+                "player2",
+                GameMove::Rock,
+                GameMove::Rock,
             );
+        }
 
-            let _scissor_move_hash = format!(
-                "{:x}",
-                Sha256::digest(format!("{}{}", GameMove::Scissors, "1").as_bytes())
-            );
+        let standings = get_league_standings(deps.as_ref(), 1).unwrap();
+        assert_eq!(standings.status, LeagueStatus::Complete);
+        for standing in standings.standings {
+            assert_eq!((standing.points, standing.tied), (1, 1));
+        }
 
-            // create players
-            let player1_funds = mock_info(&player1_name, &coins(bet_amount, "token"));
-            let player1 = mock_info(&player1_name, &coins(0, "token"));
-            let player2_funds = mock_info(&player2_name, &coins(bet_amount, "token"));
-            let player2 = mock_info(&player2_name, &coins(0, "token"));
+        // both share first place
+        assert_eq!(
+            res.messages
+                .into_iter()
+                .map(|sub_msg| sub_msg.msg)
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("player1"),
+                    amount: coins(10, "token"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("player2"),
+                    amount: coins(10, "token"),
+                }),
+            ]
+        );
 
-            // create start game messages
-            let join_game_message = ExecuteMsg::JoinGame {
-                num_hands_to_win: 1,
-            };
+        assert_treasury_totals(&mut deps);
+    }
 
-            // player 1 join game
+    #[test]
+    fn test_hand_achievements() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // migrating seeds the defaults a contract is missing and leaves the rest alone
+        ACHIEVEMENTS.remove(&mut deps.storage, "hot_hand");
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes[1].value, "hot_hand");
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes[1].value, "");
+
+        for player in ["player1", "player2"] {
             execute(
                 deps.as_mut(),
                 mock_env(),
-                player1_funds,
-                join_game_message.clone(),
+                mock_info(player, &coins(5, "token")),
+                ExecuteMsg::JoinGame {
+                    num_hands_to_win: 5,
+                    tie_break: None,
+                    mode: None,
+                    referrer: None,
+                    reliable_only: None,
+                },
             )
             .unwrap();
+        }
 
-            // player 2 join game
-            execute(deps.as_mut(), mock_env(), player2_funds, join_game_message).unwrap();
+        // a tie breaks the streak
+        for (player1_move, player2_move) in [
+            (GameMove::Rock, GameMove::Paper),
+            (GameMove::Rock, GameMove::Rock),
+            (GameMove::Rock, GameMove::Paper),
+            (GameMove::Scissors, GameMove::Rock),
+        ] {
+            let res = play_moves(&mut deps, "player1", "player2", player1_move, player2_move);
+            assert!(!res
+                .events
+                .iter()
+                .any(|event| event.ty == "rps_achievement_unlocked"));
+        }
 
-            let player1_commit_message1 = ExecuteMsg::CommitMove {
-                player1: player1_name.clone(),
-                player2: player2_name.clone(),
-                hashed_move: rock_move_hash,
-            };
+        // the third hand in a row unlocks it before the game is over
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Paper,
+            GameMove::Scissors,
+        );
+        let unlocked = res
+            .events
+            .iter()
+            .filter(|event| event.ty == "rps_achievement_unlocked")
+            .map(|event| event.attributes[2].value.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(unlocked, vec!["hot_hand"]);
+        let res = get_achievements(deps.as_ref(), String::from("player2")).unwrap();
+        assert_eq!(res.achievements.len(), 1);
+        let res = get_game(
+            deps.as_ref(),
+            String::from("player1"),
+            String::from("player2"),
+        )
+        .unwrap();
+        assert_eq!(res.game.unwrap().result, None);
+    }
 
-            let player2_commit_message1 = ExecuteMsg::CommitMove {
-                player1: player1_name.clone(),
-                player2: player2_name.clone(),
-                hashed_move: paper_move_hash,
-            };
+    #[test]
+    fn test_max_fee() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("admin")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let fee_message = |fee_percentage: u8| ExecuteMsg::SetFeeConfig {
+            fee_percentage,
+            distribution_contract: None,
+            distribution_percentage: 0,
+            referral_percentage: 0,
+            prize_pool_percentage: 100,
+        };
+
+        // the fee can't take the whole stake, or more than the cap
+        for fee_percentage in [100, MAX_FEE_PERCENTAGE + 1] {
+            let err = execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("admin", &[]),
+                fee_message(fee_percentage),
+            )
+            .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidFeeConfig {}));
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            fee_message(MAX_FEE_PERCENTAGE),
+        )
+        .unwrap();
 
-            // player 1 commit move
+        for player in ["player1", "player2"] {
             execute(
                 deps.as_mut(),
                 mock_env(),
-                player1.clone(),
-                player1_commit_message1,
+                mock_info(player, &[coin(1, "dust"), coin(3, "token")]),
+                ExecuteMsg::JoinGame {
+                    num_hands_to_win: 1,
+                    tie_break: None,
+                    mode: None,
+                    referrer: None,
+                    reliable_only: None,
+                },
             )
             .unwrap();
+        }
 
-            // player 2 commit move
+        // the dust is too little to take a fee from, the whole fee goes to the prize pool
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Scissors,
+        );
+        let paid: Vec<_> = res
+            .messages
+            .iter()
+            .map(|sub_msg| sub_msg.msg.clone())
+            .collect();
+        assert_eq!(
+            paid,
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("player1"),
+                    amount: vec![coin(1, "dust"), coin(2, "token")],
+                });
+                2
+            ]
+        );
+        let fees = get_fees(deps.as_ref()).unwrap();
+        assert!(fees.accrued_fees.is_empty());
+        assert_eq!(fees.lifetime_fees, coins(2, "token"));
+        assert_eq!(
+            get_prize_pool(deps.as_ref()).unwrap().prize_pool,
+            coins(2, "token")
+        );
+
+        // nothing is sent for a stake with no coins left
+        assert_eq!(
+            send_asset(
+                &Addr::unchecked("player1"),
+                Asset::Coins(vec![coin(0, "dust"), coin(0, "token")])
+            )
+            .unwrap(),
+            None
+        );
+
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_side_bet_dust() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            admin: Some(String::from("admin")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetSideBetCap {
+                cap: coins(100, "token"),
+            },
+        )
+        .unwrap();
+
+        for player in ["player1", "player2"] {
             execute(
                 deps.as_mut(),
                 mock_env(),
-                player2.clone(),
-                player2_commit_message1,
+                mock_info(player, &coins(5, "token")),
+                ExecuteMsg::JoinGame {
+                    num_hands_to_win: 1,
+                    tie_break: None,
+                    mode: None,
+                    referrer: None,
+                    reliable_only: None,
+                },
+            )
+            .unwrap();
+        }
+        for (bettor, amount, on) in [
+            ("alice", 1, BetSide::Player1),
+            ("bob", 2, BetSide::Player1),
+            ("carol", 2, BetSide::Player2),
+        ] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(bettor, &coins(amount, "token")),
+                ExecuteMsg::PlaceSideBet {
+                    player1: String::from("player1"),
+                    player2: String::from("player2"),
+                    on,
+                },
             )
             .unwrap();
+        }
 
-            let player1_reveal_message1 = ExecuteMsg::RevealMove {
-                player1: player1_name.clone(),
-                player2: player2_name.clone(),
-                game_move: GameMove::Rock,
-                nonce: String::from("1"),
-            };
+        // carol's 2 split 1:2 rounds down to 0 and 1, the token left over is kept as fees
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Scissors,
+        );
+        for (bettor, amount) in [("alice", 1), ("bob", 3)] {
+            assert!(res.messages.iter().any(|sub_msg| sub_msg.msg
+                == CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from(bettor),
+                    amount: coins(amount, "token"),
+                })));
+        }
+        let fees = get_fees(deps.as_ref()).unwrap();
+        assert_eq!(fees.accrued_fees, coins(1, "token"));
+        assert_eq!(fees.lifetime_fees, coins(1, "token"));
 
-            let player2_reveal_message1 = ExecuteMsg::RevealMove {
-                player1: player1_name,
-                player2: player2_name,
-                game_move: GameMove::Paper,
-                nonce: String::from("1"),
-            };
+        assert_treasury_totals(&mut deps);
+    }
 
-            // player 1 reveal move
-            execute(deps.as_mut(), mock_env(), player1, player1_reveal_message1).unwrap();
+    #[test]
+    fn test_walkouts_slash_bonds() {
+        let mut deps = mock_dependencies(&[]);
 
-            // player 2 reveal move
-            execute(deps.as_mut(), mock_env(), player2, player2_reveal_message1).unwrap();
+        let msg = InstantiateMsg {
+            admin: Some(String::from("admin")),
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        for player in ["player1", "player2", "player3"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(10, "token")),
+                ExecuteMsg::PostPenaltyBond {},
+            )
+            .unwrap();
         }
 
-        play_hand(
-            &mut deps,
-            String::from("player1"),
-            String::from("player2"),
-            5,
+        // player3 never reveals against the house, so its bond goes to the bankroll
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &[]),
+            ExecuteMsg::SetHouse {
+                operator: String::from("house"),
+                max_bet: coins(10, "token"),
+                max_exposure: coins(10, "token"),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("admin", &coins(100, "token")),
+            ExecuteMsg::DepositBankroll {},
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("house", &[]),
+            ExecuteMsg::CommitHouseChain {
+                head: String::from("head"),
+                length: 1,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player3", &coins(5, "token")),
+            ExecuteMsg::PlayHouse {
+                hashed_move: String::from("hashed"),
+            },
+        )
+        .unwrap();
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(61);
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::ClaimHouseGame {
+                player: String::from("player3"),
+            },
+        )
+        .unwrap();
+        assert!(res.messages.is_empty());
+        assert_eq!(
+            get_house(deps.as_ref()).unwrap().bankroll,
+            coins(115, "token")
         );
-        play_hand(
-            &mut deps,
-            String::from("player3"),
-            String::from("player4"),
-            3,
+        let reliability = get_reliability(deps.as_ref(), String::from("player3")).unwrap();
+        assert_eq!(reliability.games_abandoned, 1);
+        assert!(reliability.penalty_bond.is_empty());
+
+        // player1 never moves in its league fixture, so its bond goes to player2
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::CreateLeague {
+                participants: vec![String::from("player1"), String::from("player2")],
+                entry_fee: coins(10, "token"),
+                num_hands_to_win: 1,
+                join_deadline: mock_env().block.time.nanos() + 1_000,
+                round_duration: 1_000,
+            },
+        )
+        .unwrap();
+        for player in ["player1", "player2"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(10, "token")),
+                ExecuteMsg::JoinLeague { league_id: 1 },
+            )
+            .unwrap();
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &[]),
+            ExecuteMsg::CommitMove {
+                player1: String::from("player1"),
+                player2: String::from("player2"),
+                hashed_move: String::from("hashed"),
+            },
+        )
+        .unwrap();
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(1);
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("player2", &[]),
+            ExecuteMsg::TimeoutLeagueRound { league_id: 1 },
+        )
+        .unwrap();
+        // neither scores, so the entry fees are split before the bond is paid out
+        let send = |to: &str| {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from(to),
+                amount: coins(10, "token"),
+            })
+        };
+        assert_eq!(
+            res.messages
+                .into_iter()
+                .map(|sub_msg| sub_msg.msg)
+                .collect::<Vec<_>>(),
+            vec![send("player1"), send("player2"), send("player2")]
         );
+        let reliability = get_reliability(deps.as_ref(), String::from("player1")).unwrap();
+        assert_eq!(reliability.games_abandoned, 1);
+        assert!(reliability.penalty_bond.is_empty());
+        let reliability = get_reliability(deps.as_ref(), String::from("player2")).unwrap();
+        assert_eq!(reliability.games_abandoned, 0);
+        assert_eq!(reliability.penalty_bond, coins(10, "token"));
+
+        assert_treasury_totals(&mut deps);
+    }
 
-        // index_key() over UniqueIndex works.
-        // let age_key = (I32Key::from(-50), b"".to_vec());
-        let _min_winnings_key = leaderboard()
-            .idx
-            .winnings
-            .index_key((I32Key::from(-5), b"".to_vec()));
-        let _max_winnings_key = leaderboard()
-            .idx
-            .winnings
-            .index_key((I32Key::from(5), b"".to_vec()));
-        let zero_winnings_key = leaderboard()
-            .idx
-            .winnings
-            .index_key((I32Key::from(0), b"".to_vec()));
-        let most_negative_key = leaderboard()
-            .idx
-            .winnings
-            .index_key((I32Key::from(-2147483648), b"".to_vec()));
-
-        let leaderboard_res = leaderboard()
-            .idx
-            .winnings
-            .range(
+    #[test]
+    fn test_migrate_legacy_stakes() {
+        let mut deps = mock_dependencies(&coins(15, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // a game and a queue entry as the contract stored them before stakes could be NFTs,
+        // the game indexes hold a copy of the record
+        #[derive(Serialize, serde::Deserialize)]
+        struct LegacyIndexEntry {
+            pk: Binary,
+            value: LegacyGameState,
+        }
+        let legacy_game = LegacyGameState {
+            player1: Addr::unchecked("player1"),
+            player2: Addr::unchecked("player2"),
+            player1_move: None,
+            player2_move: None,
+            player1_hands_won: 0,
+            player2_hands_won: 0,
+            hands_tied: 0,
+            bet_amount: Some(coins(5, "token")),
+            player1_bet_deposited: true,
+            player2_bet_deposited: true,
+            result: None,
+            num_hands_to_win: 1,
+            updated_at: mock_env().block.time.nanos(),
+        };
+        LEGACY_GAME_STATES
+            .save(
                 deps.as_mut().storage,
-                Some(Bound::inclusive(zero_winnings_key.clone())),
-                Some(Bound::exclusive(most_negative_key.clone())),
-                Order::Descending,
+                (b"player1", b"player2"),
+                &legacy_game,
+            )
+            .unwrap();
+        for (namespace, player) in [
+            ("gamestate__player1", "player1"),
+            ("gamestate__player2", "player2"),
+        ] {
+            Map::<&Addr, LegacyIndexEntry>::new(namespace)
+                .save(
+                    deps.as_mut().storage,
+                    &Addr::unchecked(player),
+                    &LegacyIndexEntry {
+                        pk: Binary::from((b"player1".as_ref(), b"player2".as_ref()).joined_key()),
+                        value: legacy_game.clone(),
+                    },
+                )
+                .unwrap();
+        }
+        LEGACY_UNMATCHED_PLAYERS
+            .save(
+                deps.as_mut().storage,
+                (format!("{:?}", coins(5, "token")), U8Key::new(1)),
+                &LegacyUnmatchedPlayer {
+                    address: Addr::unchecked("player3"),
+                    bet_amount: Some(coins(5, "token")),
+                    num_hands_to_win: 1,
+                },
             )
-            .collect::<StdResult<Vec<_>>>()
             .unwrap();
+        assert!(get_game_by_player(deps.as_ref(), String::from("player1")).is_err());
 
-        println!("Leaderboard Internal Res:");
-        println!("{:?}", leaderboard_res);
+        // migrating rewrites them with the bet as both players' stake
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let game_state = get_game_by_player(deps.as_ref(), String::from("player2"))
+            .unwrap()
+            .game
+            .unwrap();
+        assert_eq!(game_state.id, 0);
+        assert_eq!(game_state.player1_stake, Asset::Coins(coins(5, "token")));
+        assert_eq!(game_state.player2_stake, Asset::Coins(coins(5, "token")));
+        let treasury = get_treasury(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(treasury.game_escrow, coins(10, "token"));
+        assert_eq!(treasury.queue_deposits, coins(5, "token"));
+        assert!(treasury.solvent);
+
+        // both carry on as before
+        let res = play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Paper,
+            GameMove::Rock,
+        );
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attribute| attribute.key == "game_won" && attribute.value == "player1"));
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player4", &coins(5, "token")),
+            ExecuteMsg::JoinGame {
+                num_hands_to_win: 1,
+                tie_break: None,
+                mode: None,
+                referrer: None,
+                reliable_only: None,
+            },
+        )
+        .unwrap();
+        let game_state = get_game_by_player(deps.as_ref(), String::from("player4"))
+            .unwrap()
+            .game
+            .unwrap();
+        assert_eq!(game_state.player1, Addr::unchecked("player3"));
 
-        let leaderboard_res = leaderboard()
-            .idx
-            .winnings
-            .keys(
-                deps.as_mut().storage,
-                // None,
-                // Some(Bound::exclusive(max_winnings_key)),
-                Some(Bound::inclusive(zero_winnings_key)),
-                Some(Bound::exclusive(most_negative_key)),
-                // None,
-                Order::Descending,
+        assert_treasury_totals(&mut deps);
+    }
+
+    #[test]
+    fn test_tournament_stalled() {
+        let mut deps = mock_dependencies(&coins(40, "token"));
+
+        let msg = InstantiateMsg {
+            admin: None,
+            trophy_contract: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::CreateTournament {
+                size: 4,
+                buy_in: coins(10, "token"),
+                num_hands_to_win: 1,
+                registration_deadline: mock_env().block.time.nanos() + 1_000,
+                payout_curve: None,
+            },
+        )
+        .unwrap();
+        for player in ["player1", "player2", "player3", "player4"] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(player, &coins(10, "token")),
+                ExecuteMsg::RegisterForTournament { tournament_id: 1 },
             )
-            .collect::<Vec<_>>();
+            .unwrap();
+        }
 
-        println!("Leaderboard Keys:");
-        println!("{:?}", leaderboard_res);
+        // player2 goes through, but player3 and player4 never show up
+        play_moves(
+            &mut deps,
+            "player1",
+            "player2",
+            GameMove::Rock,
+            GameMove::Paper,
+        );
 
-        println!("End test")
+        // nobody can cancel while the game could still be played
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_nanos(TOURNAMENT_STALL_WINDOW);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::CancelTournament { tournament_id: 1 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::TournamentInProgress {}));
+
+        // once it stalls anyone can cancel the tournament, everyone gets their buy-in back
+        // and both players who stayed away abandoned the game
+        env.block.time = env.block.time.plus_seconds(1);
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("anyone", &[]),
+            ExecuteMsg::CancelTournament { tournament_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages
+                .into_iter()
+                .map(|sub_msg| sub_msg.msg)
+                .collect::<Vec<_>>(),
+            ["player1", "player2", "player3", "player4"]
+                .iter()
+                .map(|player| CosmosMsg::Bank(BankMsg::Send {
+                    to_address: player.to_string(),
+                    amount: coins(10, "token"),
+                }))
+                .collect::<Vec<_>>()
+        );
+        for (player, games_abandoned) in [("player2", 0), ("player3", 1), ("player4", 1)] {
+            let profile = leaderboard()
+                .load(deps.as_ref().storage, player.as_bytes())
+                .unwrap();
+            assert_eq!(profile.games_abandoned, games_abandoned);
+        }
+        assert!(get_game(
+            deps.as_ref(),
+            String::from("player3"),
+            String::from("player4")
+        )
+        .unwrap()
+        .game
+        .is_none());
+        let res = get_tournament(deps.as_ref(), 1).unwrap();
+        assert_eq!(res.tournament.unwrap().status, TournamentStatus::Cancelled);
+
+        // player2 is free to play elsewhere again
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("player2", &coins(5, "token")),
+            ExecuteMsg::JoinGame {
+                num_hands_to_win: 1,
+                tie_break: None,
+                mode: None,
+                referrer: None,
+                reliable_only: None,
+            },
+        )
+        .unwrap();
+
+        assert_treasury_totals(&mut deps);
     }
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, VerificationError};
 use thiserror::Error;
 
 use cw_controllers::{AdminError, HookError};
@@ -16,6 +16,146 @@ pub enum ContractError {
 
     #[error("Game 404")]
     InvalidGame {},
+
+    #[error("Season 404")]
+    InvalidSeason {},
+
+    #[error("Season already in progress")]
+    SeasonInProgress {},
+
+    #[error("Season end time must be in the future")]
+    InvalidSeasonEndTime {},
+
+    #[error("Season has not ended")]
+    SeasonNotEnded {},
+
+    #[error("Payout curve must be non-empty and add up to at most 100")]
+    InvalidPayoutCurve {},
+
+    #[error("Prizes already distributed")]
+    PrizesAlreadyDistributed {},
+
+    #[error("Tournament 404")]
+    InvalidTournament {},
+
+    #[error("Tournament size must be a power of two between 2 and 64")]
+    InvalidTournamentSize {},
+
+    #[error("Tournament registration is closed")]
+    TournamentRegistrationClosed {},
+
+    #[error("Tournament games can't end in a tie")]
+    TournamentTie {},
+
+    #[error("Tournament games are still being played")]
+    TournamentInProgress {},
+
+    #[error("Player is already in a game, queue, tournament or league")]
+    PlayerBusy {},
+
+    #[error("League 404")]
+    InvalidLeague {},
+
+    #[error("League must have between 2 and 16 distinct participants")]
+    InvalidLeagueParticipants {},
+
+    #[error("League registration is closed")]
+    LeagueRegistrationClosed {},
+
+    #[error("League round is still in progress")]
+    LeagueRoundInProgress {},
+
+    #[error("Rematch offer 404")]
+    InvalidRematchOffer {},
+
+    #[error("Rematch offer expired")]
+    RematchOfferExpired {},
+
+    #[error("Max ties must be at least 1")]
+    InvalidTieBreak {},
+
+    #[error("Achievement already exists or has no id")]
+    InvalidAchievement {},
+
+    #[error("NFT collection isn't whitelisted")]
+    InvalidNftCollection {},
+
+    #[error("Move isn't valid for this game mode")]
+    WrongGameMode {},
+
+    #[error("Blitz games need exactly one move for every hand that can be played")]
+    InvalidMoveSequence {},
+
+    #[error("Public key must be a 33 or 65 byte secp256k1 key")]
+    InvalidPubkey {},
+
+    #[error("Player hasn't set a public key")]
+    MissingPubkey {},
+
+    #[error("Signature doesn't match the player's public key")]
+    InvalidSignature {},
+
+    #[error("Channel state doesn't fit the game or is older than the last one applied")]
+    InvalidChannelState {},
+
+    #[error("Channel game is disputed, the rest of it is played with commits and reveals")]
+    ChannelDisputed {},
+
+    #[error("Public key can't change while the player is in a channel game")]
+    PubkeyInUse {},
+
+    #[error("Relay nonce doesn't match the player's next nonce")]
+    InvalidRelayNonce {},
+
+    #[error("Relayer fee has to come out of the bet and leave some of it")]
+    InvalidRelayerFee {},
+
+    #[error("Session key 404, or it would never be usable")]
+    InvalidSessionKey {},
+
+    #[error("Session key expired")]
+    SessionKeyExpired {},
+
+    #[error("Session key has played all the games it was granted")]
+    SessionKeyExhausted {},
+
+    #[error("Session key holds keys from both players of the game")]
+    SessionKeyAmbiguous {},
+
+    #[error("House isn't set up or has no committed moves left")]
+    HouseUnavailable {},
+
+    #[error("House games are still waiting on the house chain")]
+    HouseGamesOpen {},
+
+    #[error("Bet is over the house's max bet, exposure limit or bankroll")]
+    HouseLimitExceeded {},
+
+    #[error("Withdrawal is more than the house bankroll or would leave player funds uncovered")]
+    InsufficientBankroll {},
+
+    #[error(
+        "Fee can take at most 50 percent, its shares at most 100, with a contract to send a distribution share to"
+    )]
+    InvalidFeeConfig {},
+
+    #[error("Not enough fees accrued in that denom")]
+    InsufficientFees {},
+
+    #[error("No referral rewards to claim")]
+    NoReferralRewards {},
+
+    #[error("Side bets are closed for this game, or you already placed one")]
+    SideBetsClosed {},
+
+    #[error("Side bets on this game would go over the cap")]
+    SideBetCapExceeded {},
+
+    #[error("Reliability is too low to only play reliable opponents")]
+    Unreliable {},
+
+    #[error("Unknown reply id")]
+    UnknownReplyId {},
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
     #[error("{0}")]
@@ -23,4 +163,7 @@ pub enum ContractError {
 
     #[error("{0}")]
     Hook(#[from] HookError),
+
+    #[error("{0}")]
+    Verification(#[from] VerificationError),
 }
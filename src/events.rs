@@ -0,0 +1,131 @@
+use cosmwasm_std::{Addr, Coin, Event};
+
+use crate::state::{Asset, GameMove, GameResult, GameState};
+
+// Bump whenever an event or attribute is renamed or removed, see EVENTS.md
+pub const EVENT_SCHEMA_VERSION: &str = "1";
+
+/// Every game event starts with the game and its players
+fn game_event(ty: &str, game_state: &GameState) -> Event {
+    Event::new(ty)
+        .add_attribute("schema_version", EVENT_SCHEMA_VERSION)
+        .add_attribute("game_id", game_state.id.to_string())
+        .add_attribute("player1", game_state.player1.clone())
+        .add_attribute("player2", game_state.player2.clone())
+}
+
+/// Adds the running score of a game
+fn with_score(event: Event, game_state: &GameState) -> Event {
+    event
+        .add_attribute(
+            "player1_hands_won",
+            game_state.player1_hands_won.to_string(),
+        )
+        .add_attribute(
+            "player2_hands_won",
+            game_state.player2_hands_won.to_string(),
+        )
+        .add_attribute("hands_tied", game_state.hands_tied.to_string())
+}
+
+pub fn game_created(game_state: &GameState) -> Event {
+    let bet = match (&game_state.player1_stake, &game_state.player2_stake) {
+        (Asset::Nft(player1_nft), Asset::Nft(player2_nft)) => format!(
+            "{}:{},{}",
+            player1_nft.collection, player1_nft.token_id, player2_nft.token_id
+        ),
+        (Asset::Coins(coins), _) | (_, Asset::Coins(coins)) => coins_to_string(coins),
+    };
+
+    let mut event = game_event("rps_game_created", game_state)
+        .add_attribute("bet", bet)
+        .add_attribute("num_hands_to_win", game_state.num_hands_to_win.to_string());
+    if let Some(tournament_id) = game_state.tournament_id {
+        event = event.add_attribute("tournament_id", tournament_id.to_string());
+    }
+    if let Some(league_id) = game_state.league_id {
+        event = event.add_attribute("league_id", league_id.to_string());
+    }
+    event
+}
+
+pub fn move_committed(game_state: &GameState, player: &Addr) -> Event {
+    game_event("rps_move_committed", game_state).add_attribute("player", player.clone())
+}
+
+pub fn move_revealed(game_state: &GameState, player: &Addr) -> Event {
+    game_event("rps_move_revealed", game_state).add_attribute("player", player.clone())
+}
+
+pub fn hand_resolved(
+    game_state: &GameState,
+    player1_move: &GameMove,
+    player2_move: &GameMove,
+    hand_result: &GameResult,
+) -> Event {
+    let event = game_event("rps_hand_resolved", game_state)
+        .add_attribute("player1_move", player1_move.to_string())
+        .add_attribute("player2_move", player2_move.to_string())
+        .add_attribute("result", result_to_string(hand_result));
+    with_score(event, game_state)
+}
+
+pub fn game_ended(game_state: &GameState, game_result: &GameResult) -> Event {
+    let event = game_event("rps_game_ended", game_state)
+        .add_attribute("result", result_to_string(game_result));
+    with_score(event, game_state)
+}
+
+pub fn game_aborted(game_state: &GameState, player: &Addr) -> Event {
+    game_event("rps_game_aborted", game_state).add_attribute("player", player.clone())
+}
+
+pub fn player_queued(player: &Addr, stake: &Asset, num_hands_to_win: u8) -> Event {
+    let bet = match stake {
+        Asset::Coins(coins) => coins_to_string(coins),
+        Asset::Nft(nft) => format!("{}:{}", nft.collection, nft.token_id),
+    };
+
+    Event::new("rps_player_queued")
+        .add_attribute("schema_version", EVENT_SCHEMA_VERSION)
+        .add_attribute("player", player.clone())
+        .add_attribute("bet", bet)
+        .add_attribute("num_hands_to_win", num_hands_to_win.to_string())
+}
+
+pub fn player_left_queue(player: &Addr) -> Event {
+    Event::new("rps_player_left_queue")
+        .add_attribute("schema_version", EVENT_SCHEMA_VERSION)
+        .add_attribute("player", player.clone())
+}
+
+pub fn win_streak(player: &Addr, best_win_streak: u32) -> Event {
+    Event::new("rps_win_streak")
+        .add_attribute("schema_version", EVENT_SCHEMA_VERSION)
+        .add_attribute("player", player.clone())
+        .add_attribute("best_win_streak", best_win_streak.to_string())
+}
+
+pub fn achievement_unlocked(player: &Addr, achievement_id: &str) -> Event {
+    Event::new("rps_achievement_unlocked")
+        .add_attribute("schema_version", EVENT_SCHEMA_VERSION)
+        .add_attribute("player", player.clone())
+        .add_attribute("achievement", achievement_id)
+}
+
+fn result_to_string(game_result: &GameResult) -> &'static str {
+    match game_result {
+        GameResult::Player1Wins => "player1_wins",
+        GameResult::Player2Wins => "player2_wins",
+        GameResult::Tie => "tie",
+    }
+}
+
+/// Shows coins as a single string, e.g. 5uluna,10uusd
+pub fn coins_to_string(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(|coin| format!("{}{}", coin.amount, coin.denom))
+        .collect::<Vec<_>>()
+        .join(",")
+}
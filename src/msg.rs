@@ -1,11 +1,189 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::state::{GameMove, GameState, UnmatchedPlayer, UserProfile};
+use cosmwasm_std::{to_binary, Addr, Binary, Coin, CosmosMsg, StdResult, Uint128, WasmMsg};
+
+use crate::state::{
+    Achievement, BetSide, FeeConfig, GameMode, GameMove, GameResult, GameState, HouseChain,
+    HouseConfig, HouseGame, League, LeagueStanding, LeagueStatus, PrizeDistribution, RematchOffer,
+    Season, SessionKey, SideBet, SideBetPool, TieBreak, Tournament, UnmatchedPlayer, UserProfile,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub admin: Option<String>,
+    pub trophy_contract: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+// Mirrors the parts of the cw721-base execute message we use
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721ExecuteMsg {
+    TransferNft { recipient: String, token_id: String },
+    Mint(MintMsg),
+}
+
+// Game lifecycle events sent to every registered hook
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GameEvent {
+    GameCreated {
+        player1: Addr,
+        player2: Addr,
+        bet_amount: Vec<Coin>,
+        num_hands_to_win: u8,
+    },
+    HandResolved {
+        player1: Addr,
+        player2: Addr,
+        player1_move: GameMove,
+        player2_move: GameMove,
+        result: GameResult,
+    },
+    GameEnded {
+        player1: Addr,
+        player2: Addr,
+        result: GameResult,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GameEventHookMsg {
+    pub event: GameEvent,
+}
+
+impl GameEventHookMsg {
+    /// serializes the message
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = GameEventExecuteMsg::GameEventHook(self);
+        to_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        let msg = self.into_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+// What hook contracts have to accept
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum GameEventExecuteMsg {
+    GameEventHook(GameEventHookMsg),
+}
+
+// Sent by a cw721 contract when a token is sent to us with SendNft
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw721ReceiveMsg {
+    pub sender: String,
+    pub token_id: String,
+    pub msg: Binary,
+}
+
+// What to do with a received NFT
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveNftMsg {
+    JoinGame {
+        num_hands_to_win: u8,
+        tie_break: Option<TieBreak>,
+        mode: Option<GameMode>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintMsg {
+    pub token_id: String,
+    pub owner: String,
+    pub token_uri: Option<String>,
+    pub extension: TrophyMetadata,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TrophyMetadata {
+    pub name: String,
+    pub description: String,
+    pub attributes: Vec<Trait>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Trait {
+    pub trait_type: String,
+    pub value: String,
+}
+
+// Score of a channel game after some hands played off-chain
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ChannelState {
+    pub game_id: u64,
+    // has to go up with every state the players sign
+    pub nonce: u64,
+    pub player1_hands_won: u8,
+    pub player2_hands_won: u8,
+    pub hands_tied: u8,
+}
+
+// What players sign: the secp256k1 signature is over sha256 of this as json
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ChannelSignDoc {
+    pub contract: String,
+    pub state: ChannelState,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SignedChannelState {
+    pub state: ChannelState,
+    pub player1_signature: Binary,
+    pub player2_signature: Binary,
+}
+
+// A move a player signed for a relayer to submit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayedMove {
+    CommitMove { hashed_move: String },
+    RevealMove { game_move: GameMove, nonce: String },
+}
+
+// What players sign: the secp256k1 signature is over sha256 of this as json
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RelaySignDoc {
+    pub contract: String,
+    pub game_id: u64,
+    // the hand being played, starting from 1
+    pub hand: u32,
+    pub relayed_move: RelayedMove,
+    pub relay_nonce: u64,
+    pub fee: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RelayMoveMsg {
+    pub player1: String,
+    pub player2: String,
+    // whose move it is, the relayer is whoever sends the message
+    pub player: String,
+    pub relayed_move: RelayedMove,
+    pub relay_nonce: u64,
+    // paid to the relayer out of the player's bet
+    pub fee: Vec<Coin>,
+    pub signature: Binary,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -13,7 +191,14 @@ pub struct InstantiateMsg {
 pub enum ExecuteMsg {
     JoinGame {
         num_hands_to_win: u8,
+        tie_break: Option<TieBreak>,
+        mode: Option<GameMode>,
+        // only recorded for players joining for the first time
+        referrer: Option<String>,
+        // only match with players whose reliability is high enough as well
+        reliable_only: Option<bool>,
     },
+    ReceiveNft(Cw721ReceiveMsg),
     LeaveWaitingQueue {},
     CommitMove {
         player1: String,
@@ -26,14 +211,176 @@ pub enum ExecuteMsg {
         game_move: GameMove,
         nonce: String,
     },
+    // Blitz games commit to sha256 of the comma separated moves followed by the nonce,
+    // e.g. "Rock,Paper,Rock" + nonce, one move for each of the 2 * num_hands_to_win - 1 hands
+    RevealMoves {
+        player1: String,
+        player2: String,
+        game_moves: Vec<GameMove>,
+        nonce: String,
+    },
+    RelayMove(RelayMoveMsg),
     ClaimGame {
         player1: String,
         player2: String,
     },
-    ForfeitGame {},
+    // Refunds both players when nobody has moved in time
+    AbortGame {
+        player1: String,
+        player2: String,
+    },
+    SetPubkey {
+        pubkey: Binary,
+    },
+    SettleChannel {
+        player1: String,
+        player2: String,
+        signed_state: SignedChannelState,
+    },
+    // Moves a channel game on-chain, starting from the latest signed score if there is one
+    DisputeChannel {
+        player1: String,
+        player2: String,
+        signed_state: Option<SignedChannelState>,
+    },
+    // Session keys name the player they forfeit for
+    ForfeitGame {
+        player: Option<String>,
+    },
+    GrantSessionKey {
+        delegate: String,
+        expires_at: u64,
+        max_games: u32,
+    },
+    RevokeSessionKey {
+        delegate: String,
+    },
+    SetHouse {
+        operator: String,
+        max_bet: Vec<Coin>,
+        max_exposure: Vec<Coin>,
+    },
+    DepositBankroll {},
+    // Only takes out of the bankroll, never funds held for players
+    WithdrawBankroll {
+        amount: Vec<Coin>,
+        to: Option<String>,
+    },
+    // Replaces the house chain, the head is the last link built
+    CommitHouseChain {
+        head: String,
+        length: u64,
+    },
+    // Plays a single hand against the house, the funds are the bet
+    PlayHouse {
+        hashed_move: String,
+    },
+    RevealHouseGame {
+        game_move: GameMove,
+        nonce: String,
+    },
+    // The operator opens the next link of the chain, settling its game once the player has revealed
+    RevealHouseMove {
+        game_move: GameMove,
+        nonce: String,
+        previous_link: String,
+    },
+    ClaimHouseGame {
+        player: String,
+    },
+    OfferRematch {
+        opponent: String,
+    },
+    AcceptRematch {
+        opponent: String,
+    },
+    CancelRematch {
+        opponent: String,
+    },
+    StartSeason {
+        end_time: u64,
+    },
+    EndSeason {},
+    FundPrizePool {},
+    // The funds are the bet, a single coin
+    PlaceSideBet {
+        player1: String,
+        player2: String,
+        on: BetSide,
+    },
+    ClaimReferralRewards {},
+    PostPenaltyBond {},
+    WithdrawPenaltyBond {},
+    CreateTournament {
+        size: u32,
+        buy_in: Vec<Coin>,
+        num_hands_to_win: u8,
+        registration_deadline: u64,
+        payout_curve: Option<Vec<u8>>,
+    },
+    RegisterForTournament {
+        tournament_id: u32,
+    },
+    CancelTournament {
+        tournament_id: u32,
+    },
+    CreateLeague {
+        participants: Vec<String>,
+        entry_fee: Vec<Coin>,
+        num_hands_to_win: u8,
+        join_deadline: u64,
+        round_duration: u64,
+    },
+    JoinLeague {
+        league_id: u32,
+    },
+    CancelLeague {
+        league_id: u32,
+    },
+    TimeoutLeagueRound {
+        league_id: u32,
+    },
+    SetPayoutCurve {
+        payout_curve: Vec<u8>,
+    },
+    SetSideBetCap {
+        cap: Vec<Coin>,
+    },
+    SetFeeConfig {
+        fee_percentage: u8,
+        distribution_contract: Option<String>,
+        distribution_percentage: u8,
+        referral_percentage: u8,
+        prize_pool_percentage: u8,
+    },
+    // The distribution contract's share comes out of the amount withdrawn
+    WithdrawFees {
+        to: String,
+        denom: String,
+        amount: Uint128,
+    },
+    DistributePrizes {
+        season_id: u32,
+        limit: Option<u32>,
+    },
+    AddAchievement {
+        achievement: Achievement,
+    },
+    AddNftCollection {
+        collection: String,
+    },
+    RemoveNftCollection {
+        collection: String,
+    },
     UpdateAdmin {
         admin: Option<String>,
     },
+    AddHook {
+        addr: String,
+    },
+    RemoveHook {
+        addr: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -58,7 +405,70 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    GetCurrentSeason {},
+    GetSeasonLeaderboard {
+        season_id: u32,
+        limit: Option<u32>,
+    },
+    GetPrizePool {},
+    GetSideBets {
+        player1: String,
+        player2: String,
+    },
+    GetFees {},
+    GetReliability {
+        player: String,
+    },
+    GetReferralStats {
+        referrer: String,
+    },
+    GetPrizeDistribution {
+        season_id: u32,
+    },
+    GetTournament {
+        tournament_id: u32,
+    },
+    GetTournaments {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    GetLeague {
+        league_id: u32,
+    },
+    GetLeagueStandings {
+        league_id: u32,
+    },
+    GetRematchOffer {
+        player1: String,
+        player2: String,
+    },
+    GetSessionKey {
+        player: String,
+        delegate: String,
+    },
+    GetHouse {},
+    GetTreasury {},
+    GetHouseGame {
+        player: String,
+    },
+    GetSessionKeys {
+        player: String,
+    },
+    GetStreakLeaderboard {
+        limit: Option<u32>,
+    },
+    GetAchievements {
+        player: String,
+    },
+    GetNftCollections {},
+    GetPubkey {
+        player: String,
+    },
+    GetRelayNonce {
+        player: String,
+    },
     Admin {},
+    Hooks {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -91,3 +501,185 @@ pub struct GetLeaderboardResponse {
 pub struct GetOpenGamesResponse {
     pub open_games: Vec<UnmatchedPlayer>,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetCurrentSeasonResponse {
+    pub season: Option<Season>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetSeasonLeaderboardResponse {
+    pub season: Season,
+    pub leaderboard: Vec<UserProfile>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetStreakLeaderboardResponse {
+    pub leaderboard: Vec<UserProfile>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct UnlockedAchievement {
+    pub achievement: Achievement,
+    pub unlocked_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetAchievementsResponse {
+    pub achievements: Vec<UnlockedAchievement>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetNftCollectionsResponse {
+    pub collections: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetPrizePoolResponse {
+    pub prize_pool: Vec<Coin>,
+    pub payout_curve: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetPrizeDistributionResponse {
+    pub distribution: Option<PrizeDistribution>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetTournamentResponse {
+    pub tournament: Option<Tournament>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetTournamentsResponse {
+    pub tournaments: Vec<Tournament>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetLeagueResponse {
+    pub league: Option<League>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetLeagueStandingsResponse {
+    pub status: LeagueStatus,
+    pub standings: Vec<LeagueStanding>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetRematchOfferResponse {
+    pub rematch_offer: Option<RematchOffer>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetPubkeyResponse {
+    pub pubkey: Option<Binary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetRelayNonceResponse {
+    pub relay_nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetSessionKeyResponse {
+    pub session_key: Option<SessionKey>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetSessionKeysResponse {
+    pub session_keys: Vec<SessionKey>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetHouseResponse {
+    pub config: Option<HouseConfig>,
+    pub bankroll: Vec<Coin>,
+    pub exposure: Vec<Coin>,
+    pub chain: Option<HouseChain>,
+    pub moves_available: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetHouseGameResponse {
+    pub game: Option<HouseGame>,
+}
+
+// Every coin the contract owes, against what it holds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetTreasuryResponse {
+    pub balance: Vec<Coin>,
+    // bets in games being played, including players' bets against the house
+    pub game_escrow: Vec<Coin>,
+    // bets waiting in the queue or on a rematch offer
+    pub queue_deposits: Vec<Coin>,
+    // tournament buy-ins and league entry fees not paid out yet
+    pub competition_escrow: Vec<Coin>,
+    pub prize_pool: Vec<Coin>,
+    // season prizes locked in by a distribution that haven't been paid out yet
+    pub prize_distributions: Vec<Coin>,
+    pub accrued_fees: Vec<Coin>,
+    // referral rewards that haven't been claimed
+    pub referral_balances: Vec<Coin>,
+    pub side_bets: Vec<Coin>,
+    pub penalty_bonds: Vec<Coin>,
+    pub house_bankroll: Vec<Coin>,
+    pub house_exposure: Vec<Coin>,
+    pub liabilities: Vec<Coin>,
+    // whether the balance covers the liabilities
+    pub solvent: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetFeesResponse {
+    pub config: Option<FeeConfig>,
+    pub accrued_fees: Vec<Coin>,
+    pub lifetime_fees: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetReferralStatsResponse {
+    pub referral_count: u32,
+    pub earned: Vec<Coin>,
+    pub balance: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetSideBetsResponse {
+    pub pool: SideBetPool,
+    pub bets: Vec<SideBet>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetReliabilityResponse {
+    pub games_played: u32,
+    pub games_abandoned: u32,
+    pub games_forfeited: u32,
+    // percentage of games played to the end
+    pub reliability: u8,
+    pub penalty_bond: Vec<Coin>,
+}
@@ -1,10 +1,10 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Coin};
-use cw_controllers::Admin;
-use cw_storage_plus::I32Key;
-use cw_storage_plus::{Index, IndexList, IndexedMap, Map, MultiIndex, U8Key, UniqueIndex};
+use cosmwasm_std::{Addr, Binary, Coin, Empty, Uint128};
+use cw_controllers::{Admin, Hooks};
+use cw_storage_plus::{I32Key, U32Key, U64Key};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex, U8Key, UniqueIndex};
 
 use std::fmt;
 
@@ -25,6 +25,20 @@ impl fmt::Display for GameMove {
 pub enum PlayerMove {
     GameMove(GameMove),
     HashedMove(String),
+    // every move of a blitz game, revealed at once
+    GameMoves(Vec<GameMove>),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    // A commit and a reveal for every hand
+    #[default]
+    Classic,
+    // One commit and one reveal for the whole game
+    Blitz,
+    // Hands are played off-chain and the players settle with a score they both signed
+    Channel,
 }
 
 // Will be using this both for hand result and game result
@@ -35,9 +49,44 @@ pub enum GameResult {
     Tie,
 }
 
+// What happens once a game hits its tie cap
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TiePolicy {
+    // Both bets go back and the game counts as a draw
+    SplitPot,
+    // Both bets go back and the game doesn't count
+    Refund,
+    // The next hand decides the game, a tied hand goes to whoever revealed first
+    SuddenDeath,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Nft {
+    pub collection: Addr,
+    pub token_id: String,
+}
+
+// Anything a player can put up as a bet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Asset {
+    Coins(Vec<Coin>),
+    Nft(Nft),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TieBreak {
+    pub max_ties: u8,
+    pub policy: TiePolicy,
+}
+
 // Need to track wins and losses
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GameState {
+    // games started before ids were handed out are 0
+    #[serde(default)]
+    pub id: u64,
     pub player1: Addr,
     pub player2: Addr,
     pub player1_move: Option<PlayerMove>,
@@ -45,14 +94,43 @@ pub struct GameState {
     pub player1_hands_won: u8,
     pub player2_hands_won: u8,
     pub hands_tied: u8,
-    pub bet_amount: Vec<Coin>,
+    // the same coins for both players, or a token each from the same collection
+    pub player1_stake: Asset,
+    pub player2_stake: Asset,
     pub player1_bet_deposited: bool,
     pub player2_bet_deposited: bool,
     pub result: Option<GameResult>,
     pub num_hands_to_win: u8,
     pub updated_at: u64,
+    pub tournament_id: Option<u32>,
+    pub league_id: Option<u32>,
+    pub tie_break: Option<TieBreak>,
+    // every revealed hand, games started before this was tracked don't have it
+    #[serde(default)]
+    pub player1_moves: Vec<GameMove>,
+    #[serde(default)]
+    pub player2_moves: Vec<GameMove>,
+    #[serde(default)]
+    pub mode: GameMode,
+    // nonce of the last signed channel state applied to the game
+    #[serde(default)]
+    pub channel_nonce: u64,
+    // a disputed channel game is played out with commits and reveals
+    // once the challenge window after the dispute closes
+    #[serde(default)]
+    pub channel_disputed: bool,
+    #[serde(default)]
+    pub channel_disputed_at: u64,
+    // paid to relayers out of each player's bet
+    #[serde(default)]
+    pub player1_relayer_fees: Vec<Coin>,
+    #[serde(default)]
+    pub player2_relayer_fees: Vec<Coin>,
 }
 
+// id of the most recently started game
+pub const LATEST_GAME_ID: Item<u64> = Item::new("latest_game_id");
+
 pub struct GameIndexes<'a> {
     pub player1: UniqueIndex<'a, Addr, GameState>,
     pub player2: UniqueIndex<'a, Addr, GameState>,
@@ -67,8 +145,8 @@ impl<'a> IndexList<GameState> for GameIndexes<'a> {
 
 pub fn game_states<'a>() -> IndexedMap<'a, (&'a [u8], &'a [u8]), GameState, GameIndexes<'a>> {
     let indexes = GameIndexes {
-        player1: UniqueIndex::new(|d: &GameState| (d.player1.clone()), "gamestate__player1"),
-        player2: UniqueIndex::new(|d: &GameState| (d.player2.clone()), "gamestate__player2"),
+        player1: UniqueIndex::new(|d: &GameState| d.player1.clone(), "gamestate__player1"),
+        player2: UniqueIndex::new(|d: &GameState| d.player2.clone(), "gamestate__player2"),
     };
     IndexedMap::new("gamestate", indexes)
 }
@@ -76,31 +154,107 @@ pub fn game_states<'a>() -> IndexedMap<'a, (&'a [u8], &'a [u8]), GameState, Game
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct UnmatchedPlayer {
     pub address: Addr,
-    pub bet_amount: Vec<Coin>,
+    pub stake: Asset,
     pub num_hands_to_win: u8,
+    pub tie_break: Option<TieBreak>,
+    #[serde(default)]
+    pub mode: GameMode,
+    // only matched with other players who asked for reliable opponents
+    #[serde(default)]
+    pub reliable_only: bool,
 }
 
 // unmatched players
 pub const UNMATCHED_PLAYERS: Map<(String, U8Key), UnmatchedPlayer> = Map::new("unmatched_players");
 
+// Games and queue entries as stored before stakes could be NFTs, only read when migrating.
+// Newer records parse too, without a bet amount
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LegacyGameState {
+    pub player1: Addr,
+    pub player2: Addr,
+    pub player1_move: Option<PlayerMove>,
+    pub player2_move: Option<PlayerMove>,
+    pub player1_hands_won: u8,
+    pub player2_hands_won: u8,
+    pub hands_tied: u8,
+    #[serde(default)]
+    pub bet_amount: Option<Vec<Coin>>,
+    pub player1_bet_deposited: bool,
+    pub player2_bet_deposited: bool,
+    pub result: Option<GameResult>,
+    pub num_hands_to_win: u8,
+    pub updated_at: u64,
+}
+
+pub const LEGACY_GAME_STATES: Map<(&[u8], &[u8]), LegacyGameState> = Map::new("gamestate");
+// the game indexes hold a copy of the record, so legacy entries are cleared before rewriting
+pub const LEGACY_GAME_PLAYER1_INDEX: Map<&Addr, Empty> = Map::new("gamestate__player1");
+pub const LEGACY_GAME_PLAYER2_INDEX: Map<&Addr, Empty> = Map::new("gamestate__player2");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LegacyUnmatchedPlayer {
+    pub address: Addr,
+    #[serde(default)]
+    pub bet_amount: Option<Vec<Coin>>,
+    pub num_hands_to_win: u8,
+}
+
+pub const LEGACY_UNMATCHED_PLAYERS: Map<(String, U8Key), LegacyUnmatchedPlayer> =
+    Map::new("unmatched_players");
+
 // ADMIN controller
 pub const ADMIN: Admin = Admin::new("admin");
 
+// contracts notified about game lifecycle events
+pub const HOOKS: Hooks = Hooks::new("hooks");
+
+// cw721 collections players can bet NFTs from
+pub const NFT_COLLECTIONS: Map<&Addr, Empty> = Map::new("nft_collections");
+
+// cw721 contract trophies are minted on, if any
+pub const TROPHY_CONTRACT: Item<Addr> = Item::new("trophy_contract");
+
+// A game result from one player's point of view
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayerResult {
+    Won,
+    Lost,
+    Drew,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct UserProfile {
     pub address: Addr,
     pub num_games_played: u32,
     pub num_games_won: u32,
+    // profiles saved before draws were recorded don't have this yet
+    #[serde(default)]
+    pub num_games_drawn: u32,
     pub winnings: i32,
+    // positive for a win streak, negative for a losing streak
+    #[serde(default)]
+    pub current_streak: i32,
+    #[serde(default)]
+    pub best_win_streak: u32,
+    #[serde(default)]
+    pub last_result: Option<PlayerResult>,
+    // games lost by walking away and having them claimed
+    #[serde(default)]
+    pub games_abandoned: u32,
+    #[serde(default)]
+    pub games_forfeited: u32,
 }
 
 pub struct LeaderboardIndexes<'a> {
     pub winnings: MultiIndex<'a, (I32Key, Vec<u8>), UserProfile>,
+    pub best_win_streak: MultiIndex<'a, (U32Key, Vec<u8>), UserProfile>,
 }
 
 impl<'a> IndexList<UserProfile> for LeaderboardIndexes<'a> {
     fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<UserProfile>> + '_> {
-        let v: Vec<&dyn Index<UserProfile>> = vec![&self.winnings];
+        let v: Vec<&dyn Index<UserProfile>> = vec![&self.winnings, &self.best_win_streak];
         Box::new(v.into_iter())
     }
 }
@@ -112,6 +266,362 @@ pub fn leaderboard<'a>() -> IndexedMap<'a, &'a [u8], UserProfile, LeaderboardInd
             "leaderboard",
             "leaderboard__winnings",
         ),
+        best_win_streak: MultiIndex::new(
+            |d: &UserProfile, k| (U32Key::new(d.best_win_streak), k),
+            "leaderboard",
+            "leaderboard__best_win_streak",
+        ),
     };
     IndexedMap::new("leaderboard", indexes)
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Season {
+    pub id: u32,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub ended: bool,
+}
+
+// seasons by id
+pub const SEASONS: Map<U32Key, Season> = Map::new("seasons");
+
+// id of the most recently started season
+pub const LATEST_SEASON_ID: Item<u32> = Item::new("latest_season_id");
+
+// A user profile scoped to a single season
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SeasonProfile {
+    pub season_id: u32,
+    pub profile: UserProfile,
+}
+
+pub struct SeasonLeaderboardIndexes<'a> {
+    pub winnings: MultiIndex<'a, (U32Key, I32Key, Vec<u8>), SeasonProfile>,
+}
+
+impl<'a> IndexList<SeasonProfile> for SeasonLeaderboardIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<SeasonProfile>> + '_> {
+        let v: Vec<&dyn Index<SeasonProfile>> = vec![&self.winnings];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn season_leaderboard<'a>(
+) -> IndexedMap<'a, (U32Key, &'a [u8]), SeasonProfile, SeasonLeaderboardIndexes<'a>> {
+    let indexes = SeasonLeaderboardIndexes {
+        winnings: MultiIndex::new(
+            |d: &SeasonProfile, k| (U32Key::new(d.season_id), I32Key::new(d.profile.winnings), k),
+            "season_leaderboard",
+            "season_leaderboard__winnings",
+        ),
+    };
+    IndexedMap::new("season_leaderboard", indexes)
+}
+
+// Funds waiting to be paid out to the top players of a season
+pub const PRIZE_POOL: Item<Vec<Coin>> = Item::new("prize_pool");
+
+// Percentage of the prize pool paid to each rank, starting from first place
+pub const PAYOUT_CURVE: Item<Vec<u8>> = Item::new("payout_curve");
+
+// Progress of paying out a finished season, since it can take several transactions
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PrizeDistribution {
+    pub season_id: u32,
+    pub prize_pool: Vec<Coin>,
+    pub payout_curve: Vec<u8>,
+    pub num_paid: u32,
+    pub last_paid_key: Option<Vec<u8>>,
+    pub complete: bool,
+}
+
+pub const PRIZE_DISTRIBUTIONS: Map<U32Key, PrizeDistribution> = Map::new("prize_distributions");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub enum TournamentStatus {
+    Registration,
+    InProgress,
+    Complete,
+    Cancelled,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Tournament {
+    pub id: u32,
+    pub creator: Addr,
+    pub size: u32,
+    pub buy_in: Vec<Coin>,
+    pub num_hands_to_win: u8,
+    pub registration_deadline: u64,
+    // Percentage of the prize pool for the champion, the runner up,
+    // then shared by the losers of each earlier round
+    pub payout_curve: Vec<u8>,
+    pub players: Vec<Addr>,
+    // bracket[0] is the seeded first round, bracket[n + 1][i] is the winner
+    // of the match between bracket[n][2 * i] and bracket[n][2 * i + 1]
+    pub bracket: Vec<Vec<Option<Addr>>>,
+    pub status: TournamentStatus,
+}
+
+// tournaments by id
+pub const TOURNAMENTS: Map<U32Key, Tournament> = Map::new("tournaments");
+
+// id of the most recently created tournament
+pub const LATEST_TOURNAMENT_ID: Item<u32> = Item::new("latest_tournament_id");
+
+// players still registered or playing in a tournament
+pub const TOURNAMENT_PLAYERS: Map<&Addr, u32> = Map::new("tournament_players");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub enum LeagueStatus {
+    Registration,
+    InProgress,
+    Complete,
+    Cancelled,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LeagueFixture {
+    pub player1: Addr,
+    pub player2: Addr,
+    pub result: Option<GameResult>,
+    pub timed_out: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LeagueStanding {
+    pub player: Addr,
+    pub points: u32,
+    pub played: u32,
+    pub won: u32,
+    pub tied: u32,
+    pub lost: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct League {
+    pub id: u32,
+    pub creator: Addr,
+    pub participants: Vec<Addr>,
+    pub paid: Vec<Addr>,
+    pub entry_fee: Vec<Coin>,
+    pub num_hands_to_win: u8,
+    pub join_deadline: u64,
+    pub round_duration: u64,
+    // schedule[n] holds the fixtures of round n, nobody plays twice in a round
+    pub schedule: Vec<Vec<LeagueFixture>>,
+    pub current_round: u32,
+    pub round_started_at: u64,
+    pub standings: Vec<LeagueStanding>,
+    pub status: LeagueStatus,
+}
+
+// leagues by id
+pub const LEAGUES: Map<U32Key, League> = Map::new("leagues");
+
+// id of the most recently created league
+pub const LATEST_LEAGUE_ID: Item<u32> = Item::new("latest_league_id");
+
+// players who have paid into a league that isn't finished
+pub const LEAGUE_PLAYERS: Map<&Addr, u32> = Map::new("league_players");
+
+// Left behind by a finished game so both players can play again without queueing
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RematchOffer {
+    pub player1: Addr,
+    pub player2: Addr,
+    pub bet_amount: Vec<Coin>,
+    pub num_hands_to_win: u8,
+    pub tie_break: Option<TieBreak>,
+    #[serde(default)]
+    pub mode: GameMode,
+    pub expires_at: u64,
+    pub offered_by: Option<Addr>,
+}
+
+pub const REMATCH_OFFERS: Map<(&[u8], &[u8]), RematchOffer> = Map::new("rematch_offers");
+
+// A hot key allowed to play moves for a player, it can't touch their funds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SessionKey {
+    pub player: Addr,
+    pub delegate: Addr,
+    pub expires_at: u64,
+    pub max_games: u32,
+    // games the key has played in so far
+    pub game_ids: Vec<u64>,
+}
+
+// Keyed by player and delegate
+pub const SESSION_KEYS: Map<(&[u8], &[u8]), SessionKey> = Map::new("session_keys");
+
+// What a player has to do to unlock an achievement
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AchievementCriteria {
+    GamesWon { count: u32 },
+    WinStreak { length: u32 },
+    // beat someone ranked this high or higher on the leaderboard
+    BeatTopPlayer { rank: u32 },
+    // win a game playing nothing but this move
+    SingleMoveWin { game_move: GameMove },
+    // win this many hands in a row within a game
+    HandStreak { length: u8 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Achievement {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub criteria: AchievementCriteria,
+}
+
+// achievement definitions by id
+pub const ACHIEVEMENTS: Map<&str, Achievement> = Map::new("achievements");
+
+// when each player unlocked each achievement
+pub const UNLOCKED_ACHIEVEMENTS: Map<(&[u8], &[u8]), u64> = Map::new("unlocked_achievements");
+
+// secp256k1 public keys players sign channel states with
+pub const PUBKEYS: Map<&Addr, Binary> = Map::new("pubkeys");
+
+// next nonce each player has to sign a relayed move with
+pub const RELAY_NONCES: Map<&Addr, u64> = Map::new("relay_nonces");
+
+// Who plays for the house and how much it's allowed to put at stake
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HouseConfig {
+    pub operator: Addr,
+    // most the house matches in a single game
+    pub max_bet: Vec<Coin>,
+    // most the house has locked up in games at once
+    pub max_exposure: Vec<Coin>,
+}
+
+pub const HOUSE_CONFIG: Item<HouseConfig> = Item::new("house_config");
+
+// What the house can still put up against players
+pub const HOUSE_BANKROLL: Item<Vec<Coin>> = Item::new("house_bankroll");
+
+// House stakes locked in games that are still being played
+pub const HOUSE_EXPOSURE: Item<Vec<Coin>> = Item::new("house_exposure");
+
+// The operator's committed house moves as a hash chain, each link is
+// sha256(move ‖ nonce ‖ previous link) so opening one link gives the next one to open
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct HouseChain {
+    // link the next house reveal has to open
+    pub head: String,
+    // number of moves the chain was committed with
+    pub length: u64,
+    // chain positions handed out to games
+    pub dealt: u64,
+    // chain positions opened so far, always in order
+    pub revealed: u64,
+}
+
+pub const HOUSE_CHAIN: Item<HouseChain> = Item::new("house_chain");
+
+// Player whose game is waiting on each dealt chain position that hasn't been opened
+pub const HOUSE_CHAIN_GAMES: Map<U64Key, Addr> = Map::new("house_chain_games");
+
+// A single hand against the house, the house matches the player's bet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HouseGame {
+    pub id: u64,
+    pub player: Addr,
+    pub bet_amount: Vec<Coin>,
+    pub player_move: PlayerMove,
+    // position in the house chain holding the house's move
+    pub chain_position: u64,
+    pub updated_at: u64,
+}
+
+pub const HOUSE_GAMES: Map<&Addr, HouseGame> = Map::new("house_games");
+
+// Fee taken out of winnings, kept in the contract until the admin withdraws it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeConfig {
+    // percentage of each coin stake paid to the winner
+    pub fee_percentage: u8,
+    // contract that gets its share of every fee withdrawal, e.g. for stakers
+    pub distribution_contract: Option<Addr>,
+    pub distribution_percentage: u8,
+    // share of the fee on a referred player's stake that goes to their referrer
+    #[serde(default)]
+    pub referral_percentage: u8,
+    // share of the contract's part of the fee that funds the season prize pool
+    #[serde(default)]
+    pub prize_pool_percentage: u8,
+}
+
+pub const FEE_CONFIG: Item<FeeConfig> = Item::new("fee_config");
+
+// Fees that haven't been withdrawn yet, by denom
+pub const ACCRUED_FEES: Map<&str, Uint128> = Map::new("accrued_fees");
+
+// Every fee ever taken, by denom
+pub const LIFETIME_FEES: Map<&str, Uint128> = Map::new("lifetime_fees");
+
+// Who referred each player, recorded the first time they join
+pub const REFERRALS: Map<&Addr, Addr> = Map::new("referrals");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ReferralStats {
+    pub referral_count: u32,
+    // every reward ever credited
+    pub earned: Vec<Coin>,
+    // rewards that haven't been claimed yet
+    pub balance: Vec<Coin>,
+}
+
+pub const REFERRAL_STATS: Map<&Addr, ReferralStats> = Map::new("referral_stats");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BetSide {
+    Player1,
+    Player2,
+}
+
+// A spectator's bet on who wins a game
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SideBet {
+    pub bettor: Addr,
+    pub on: BetSide,
+    pub amount: Coin,
+}
+
+// Side bets by game id and bettor
+pub const SIDE_BETS: Map<(U64Key, &Addr), SideBet> = Map::new("side_bets");
+
+// Everything bet on either side of a game
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct SideBetPool {
+    pub player1: Vec<Coin>,
+    pub player2: Vec<Coin>,
+}
+
+pub const SIDE_BET_POOLS: Map<U64Key, SideBetPool> = Map::new("side_bet_pools");
+
+// Most that can be bet on a single game across both sides, no cap means no side bets
+pub const SIDE_BET_CAP: Item<Vec<Coin>> = Item::new("side_bet_cap");
+
+// Bonds players post as a promise not to walk away from games, slashed to the opponent if they do
+pub const PENALTY_BONDS: Map<&Addr, Vec<Coin>> = Map::new("penalty_bonds");
+
+// Running totals of the funds held in the records above, kept in step whenever one is saved
+// or removed so the treasury never has to walk them
+// stakes in games being played, including players' bets against the house
+pub const GAME_ESCROW: Item<Vec<Coin>> = Item::new("game_escrow");
+// bets waiting in the queue or on a rematch offer
+pub const QUEUE_DEPOSITS: Item<Vec<Coin>> = Item::new("queue_deposits");
+// tournament buy-ins and league entry fees not paid out yet
+pub const COMPETITION_ESCROW: Item<Vec<Coin>> = Item::new("competition_escrow");
+pub const SIDE_BET_TOTAL: Item<Vec<Coin>> = Item::new("side_bet_total");
+pub const PENALTY_BOND_TOTAL: Item<Vec<Coin>> = Item::new("penalty_bond_total");
+pub const REFERRAL_TOTAL: Item<Vec<Coin>> = Item::new("referral_total");
+// season prizes locked in by a distribution that haven't been paid out yet
+pub const PRIZE_DISTRIBUTION_TOTAL: Item<Vec<Coin>> = Item::new("prize_distribution_total");